@@ -44,6 +44,15 @@ def parse_port_arg() -> int:
     return args.port
 
 
+def parse_host_arg() -> str:
+    """解析命令行 --host 参数"""
+    import argparse
+    parser = argparse.ArgumentParser(add_help=False)
+    parser.add_argument("--host", default="127.0.0.1")
+    args, _ = parser.parse_known_args()
+    return args.host
+
+
 def detect_running_mode() -> str:
     """
     检测当前运行模式
@@ -175,13 +184,14 @@ def start_api_server(**kwargs):
     """Start the FastAPI server"""
     global server_instance
     port = kwargs.get("port", find_available_port())
+    host = kwargs.get("host", "127.0.0.1")
     
     try:
         if server_instance is None:
             print(f"[{mode_label}] Starting API server on port {port}...", flush=True)
-            print(f"[{mode_label}] Server will be available at http://127.0.0.1:{port}", flush=True)
+            print(f"[{mode_label}] Server will be available at http://{host}:{port}", flush=True)
             
-            config = Config(app, host="127.0.0.1", port=port, log_level="info")
+            config = Config(app, host=host, port=port, log_level="info")
             server_instance = Server(config)
             asyncio.run(server_instance.serve())
         else:
@@ -194,7 +204,11 @@ def stdin_loop():
     print(f"[{mode_label}] Waiting for commands...", flush=True)
     while True:
         try:
-            user_input = sys.stdin.readline().strip()
+            line = sys.stdin.readline()
+            if not line:
+                # stdin 已关闭（宿主退出或 detached 模式下释放了管道），停止监听但保持服务运行
+                break
+            user_input = line.strip()
             if user_input == "sidecar shutdown":
                 print(f"[{mode_label}] Received 'sidecar shutdown' command.", flush=True)
                 os.kill(os.getpid(), signal.SIGINT)
@@ -218,6 +232,7 @@ def start_input_thread():
 def run_standalone():
     """Run in standalone mode with uvicorn auto-reload"""
     requested_port = parse_port_arg()
+    host = parse_host_arg()
     port = find_available_port(requested_port)
     
     print(f"🚀 Starting standalone development mode")
@@ -229,7 +244,7 @@ def run_standalone():
     try:
         uvicorn.run(
             "main:app", 
-            host="127.0.0.1", 
+            host=host, 
             port=port, 
             reload=True,
             reload_dirs=["./"],
@@ -247,7 +262,7 @@ def run_sidecar():
     """Run in sidecar mode with stdin handling"""
     requested_port = parse_port_arg()
     start_input_thread()
-    start_api_server(port=requested_port)
+    start_api_server(port=requested_port, host=parse_host_arg())
 
 
 def run_pywebview():
@@ -1,14 +1,18 @@
-use std::sync::{Arc, Mutex};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::process::{Child, Command};
 use std::net::TcpListener;
 use std::fs::{self, OpenOptions};
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 // 非 Windows 平台需要 Stdio
 #[cfg(not(target_os = "windows"))]
 use std::process::Stdio;
 use tauri::{Emitter, Manager, RunEvent, Url, WebviewUrl};
 use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
 
 // ============== Dev Mode 状态 ==============
 
@@ -68,43 +72,163 @@ impl DevModeState {
 use std::os::windows::process::CommandExt;
 #[cfg(target_os = "windows")]
 const CREATE_NO_WINDOW: u32 = 0x08000000;
+#[cfg(target_os = "windows")]
+const PROCESS_QUERY_LIMITED_INFORMATION: u32 = 0x1000;
+
+#[cfg(target_os = "windows")]
+extern "system" {
+    fn OpenProcess(desired_access: u32, inherit_handle: i32, process_id: u32) -> *mut std::ffi::c_void;
+    fn GetProcessHandleCount(process: *mut std::ffi::c_void, handle_count: *mut u32) -> i32;
+    fn CloseHandle(handle: *mut std::ffi::c_void) -> i32;
+}
+
+// ============== 事件 ==============
+
+/// 发送给前端的事件名
+mod events {
+    pub const READY: &str = "python-ready";
+    pub const ERROR: &str = "python-error";
+    pub const WARMED: &str = "python-warmed";
+    pub const FALLBACK: &str = "python-fallback";
+    pub const RESOURCES: &str = "python-resources";
+    pub const HEARTBEAT: &str = "python-heartbeat";
+    pub const IDLE_STOPPED: &str = "python-idle-stopped";
+    pub const TERMINATED: &str = "python-terminated";
+    pub const UPGRADE_OUTPUT: &str = "python-upgrade-output";
+    pub const STARTUP_PROGRESS: &str = "python-startup-progress";
+    pub const PORT_CONFLICT: &str = "port-conflict";
+    pub const CONFIG_WARNING: &str = "config-warning";
+    pub const EXTERNAL_BIND: &str = "external-bind-warning";
+}
+
+/// 事件说明（名称、用途、示例负载）
+#[derive(Debug, Clone, Serialize)]
+struct EventDescriptor {
+    name: &'static str,
+    description: &'static str,
+    example: serde_json::Value,
+}
+
+/// 所有会发送给前端的事件
+fn event_catalog() -> Vec<EventDescriptor> {
+    use serde_json::json;
+    
+    vec![
+        EventDescriptor {
+            name: events::READY,
+            description: "Backend passed the configured readiness check (or an existing service was reused); payload is the port.",
+            example: json!(8009),
+        },
+        EventDescriptor {
+            name: events::ERROR,
+            description: "Backend failed to start; payload carries an error code and message.",
+            example: json!({ "code": "PACKAGE_NOT_INSTALLED", "message": "aestiv package not found. Run: pip install -e ./src-python" }),
+        },
+        EventDescriptor {
+            name: events::WARMED,
+            description: "Configured warmup request finished after the backend became ready.",
+            example: json!({ "status": 200, "elapsed_ms": 840 }),
+        },
+        EventDescriptor {
+            name: events::FALLBACK,
+            description: "Configured interpreter failed and another detected interpreter was used.",
+            example: json!({ "from": "python", "to": "/usr/bin/python3" }),
+        },
+        EventDescriptor {
+            name: events::RESOURCES,
+            description: "Periodic CPU/memory sample of the backend process (resource_monitor).",
+            example: json!({ "cpu_percent": 3.5, "memory_bytes": 73400320 }),
+        },
+        EventDescriptor {
+            name: events::HEARTBEAT,
+            description: "Periodic alive signal while the backend process runs (heartbeat_interval_ms).",
+            example: json!({ "pid": 12345, "uptime_ms": 60000 }),
+        },
+        EventDescriptor {
+            name: events::TERMINATED,
+            description: "Backend process exit observed during shutdown; requested is false if it had already exited on its own.",
+            example: json!({ "pid": 12345, "code": null, "signal": 15, "requested": true, "raw": "signal: 15 (SIGTERM)" }),
+        },
+        EventDescriptor {
+            name: events::STARTUP_PROGRESS,
+            description: "Backend startup entered a new phase; ready and failed are terminal.",
+            example: json!({ "phase": "checking_package", "detail": "python", "timestamp": 1700000000000u64 }),
+        },
+        EventDescriptor {
+            name: events::UPGRADE_OUTPUT,
+            description: "One line of pip output while upgrade_aestiv runs.",
+            example: json!({ "stream": "stdout", "line": "Successfully installed aestiv-0.2.0" }),
+        },
+        EventDescriptor {
+            name: events::IDLE_STOPPED,
+            description: "Backend was stopped after idle_timeout_ms without activity; next note_backend_activity restarts it.",
+            example: json!({ "idle_ms": 600000 }),
+        },
+        EventDescriptor {
+            name: events::PORT_CONFLICT,
+            description: "Configured port is held by an unrelated program that was left running.",
+            example: json!({
+                "owner": { "port": 8009, "pid": 4321, "name": "nginx", "cmdline": "nginx -g daemon off;", "kind": "foreign" },
+                "message": "Port 8009 is used by another program (nginx, PID 4321). aestivus will not stop it and is starting on a different port.",
+            }),
+        },
+        EventDescriptor {
+            name: events::CONFIG_WARNING,
+            description: "Config file had unknown keys, could not be parsed, or was shadowed by another config file.",
+            example: json!({ "path": "../config/python.json", "unknown_keys": [], "error": null, "shadowed_by": "config/python.json" }),
+        },
+        EventDescriptor {
+            name: events::EXTERNAL_BIND,
+            description: "Backend is bound to a non-loopback host (allow_external_bind); sent once per app run.",
+            example: json!({ "host": "0.0.0.0", "port": 8009 }),
+        },
+    ]
+}
 
 // ============== 实例管理 ==============
 
-/// 检查端口是否被占用
-fn is_port_in_use(port: u16) -> bool {
-    TcpListener::bind(("127.0.0.1", port)).is_err()
+/// 检查端口是否被占用（host 取 PythonConfig::connect_host）
+fn is_port_in_use(host: &str, port: u16) -> bool {
+    TcpListener::bind((host, port)).is_err()
 }
 
-/// 检查 8009 端口是否有 aestivus 服务在运行（通过 HTTP 请求）
-fn check_aestivus_service(port: u16) -> bool {
-    // 使用同步 HTTP 请求检查服务
-    let url = format!("http://127.0.0.1:{}/health", port);
+/// 检查 8009 端口是否有 aestivus 服务在运行（通过 HTTP 请求健康检查路径）
+/// 配置了 auth_token 时附带 Bearer 令牌；直接用 TcpStream 发请求，令牌不会出现在子进程命令行里
+fn check_aestivus_service(config: &PythonConfig, port: u16) -> bool {
+    use std::io::{Read, Write};
+    use std::net::ToSocketAddrs;
     
-    #[cfg(target_os = "windows")]
-    {
-        // Windows: 使用 curl 或 PowerShell
-        if let Ok(output) = Command::new("curl")
-            .args(["-s", "-m", "1", &url])
-            .output()
-        {
-            let body = String::from_utf8_lossy(&output.stdout);
-            return body.contains("aestiv") || body.contains("ok");
-        }
-    }
+    let timeout = Duration::from_secs(1);
+    let Some(addr) = (config.connect_host(), port).to_socket_addrs().ok().and_then(|mut addrs| addrs.next()) else {
+        return false;
+    };
+    let Ok(mut stream) = std::net::TcpStream::connect_timeout(&addr, timeout) else {
+        return false;
+    };
+    let _ = stream.set_read_timeout(Some(timeout));
+    let _ = stream.set_write_timeout(Some(timeout));
     
-    #[cfg(not(target_os = "windows"))]
-    {
-        if let Ok(output) = Command::new("curl")
-            .args(["-s", "-m", "1", &url])
-            .output()
-        {
-            let body = String::from_utf8_lossy(&output.stdout);
-            return body.contains("aestiv") || body.contains("ok");
-        }
+    // HTTP/1.0：服务端写完响应后关闭连接，读到 EOF 即可
+    let base_url = config.connect_base_url(port);
+    let mut request = format!(
+        "GET {} HTTP/1.0\r\nHost: {}\r\n",
+        config.health_path,
+        base_url.trim_start_matches("http://")
+    );
+    if let Some(token) = &config.auth_token {
+        request.push_str(&format!("Authorization: Bearer {}\r\n", token));
+    }
+    request.push_str("\r\n");
+    if stream.write_all(request.as_bytes()).is_err() {
+        return false;
     }
     
-    false
+    // 超时也保留已读到的部分
+    let mut response = Vec::new();
+    let _ = stream.read_to_end(&mut response);
+    let response = String::from_utf8_lossy(&response);
+    let body = response.split_once("\r\n\r\n").map(|(_, body)| body).unwrap_or("");
+    body.contains("aestiv") || body.contains("ok")
 }
 
 /// 获取锁文件路径
@@ -166,9 +290,9 @@ fn release_primary_lock() {
 }
 
 /// 为多开实例找一个可用端口
-fn find_available_port(start_port: u16) -> u16 {
+fn find_available_port(host: &str, start_port: u16) -> u16 {
     for port in start_port..start_port + 100 {
-        if !is_port_in_use(port) {
+        if !is_port_in_use(host, port) {
             return port;
         }
     }
@@ -178,20 +302,172 @@ fn find_available_port(start_port: u16) -> u16 {
 // ============== Python 配置 ==============
 
 /// Python 后端配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
 pub struct PythonConfig {
     /// Python 解释器路径（默认 "python"）
     pub python_path: String,
     /// API 端口（默认 8009）
     pub port: u16,
+    /// 端口被外部程序占用时是否改用其他可用端口（默认 true，false 时放弃启动）
+    pub auto_port: bool,
     /// 监听地址（默认 "127.0.0.1"）
     pub host: String,
+    /// 后端接口鉴权令牌：启动时通过 AESTIV_AUTH_TOKEN 环境变量传给后端，请求时作为 Bearer 令牌发送
+    pub auth_token: Option<String>,
+    /// 是否允许监听非回环地址（如 "0.0.0.0"），默认 false
+    pub allow_external_bind: bool,
     /// 是否自动重启（默认 true）
     pub auto_restart: bool,
+    /// 应用启动时是否自动启动后端（默认 true）
+    pub auto_start: bool,
+    /// 启动期间显示启动画面，后端就绪后关闭并显示主窗口（默认 false）
+    pub splash: bool,
+    /// 关闭应用时保留后端运行，下次启动时重新接管（默认 false）
+    pub detached: bool,
     /// 启动超时时间（毫秒，默认 10000）
     pub startup_timeout_ms: u64,
+    /// 启动后确认进程没有立即退出的等待时间（毫秒，默认 500，0 表示不确认）
+    pub spawn_confirm_ms: u64,
     /// 开发模式（启用热重载）
     pub dev_mode: bool,
+    /// 优雅关闭等待时间（毫秒，默认 3000），超时后强制结束
+    pub graceful_shutdown_timeout_ms: u64,
+    /// 强制结束进程后等待其真正退出的最长时间（毫秒，默认 2000）
+    pub post_kill_timeout_ms: u64,
+    /// 自定义关闭步骤，按顺序执行直到进程退出（默认：stdin 请求退出 → SIGTERM → SIGKILL）
+    pub kill_sequence: Option<Vec<KillStep>>,
+    /// 后端就绪后发送的预热请求（默认不发送）
+    pub warmup: Option<WarmupConfig>,
+    /// 包装启动命令，如 ["uv", "run"] 或 ["poetry", "run"]，实际执行 `launcher... python_path -m module`
+    pub launcher: Option<Vec<String>>,
+    /// 虚拟环境目录，设置后使用其中的解释器，并为子进程设置 VIRTUAL_ENV 与 PATH（默认不启用）
+    pub venv_path: Option<PathBuf>,
+    /// 后端进程的环境：inherit 继承应用的全部环境变量（默认），clean 只保留基本变量
+    pub env_mode: EnvMode,
+    /// 额外设置给后端进程的环境变量
+    pub env: BTreeMap<String, String>,
+    /// 以 `-m {module}` 启动的 Python 模块名（默认 "aestiv"）
+    pub module: String,
+    /// upgrade_aestiv 执行 `pip install --upgrade {package_spec}` 时的包规格（默认 "-e ../src-python" 即重新以开发模式安装本地后端包）
+    pub package_spec: String,
+    /// 健康检查路径（默认 "/health"）
+    pub health_path: String,
+    /// 启动就绪检查路径（默认与 health_path 相同）
+    pub readiness_path: Option<String>,
+    /// 判断后端启动完成（发送 python-ready）的方式（默认 tcp_connect）
+    pub readiness: ReadinessStrategy,
+    /// API 文档页面路径（默认 "/docs"）
+    pub docs_path: String,
+    /// OpenAPI 规范路径（默认 "/openapi.json"）
+    pub openapi_path: String,
+    /// 关闭前通知后端停止接收新请求的路径（POST，默认不启用）
+    pub drain_path: Option<String>,
+    /// 多个配置文件同时存在时是否按顺序合并（后面的覆盖前面的），默认 false 仅第一个生效
+    pub merge_configs: bool,
+    /// 归 aestivus 管理、清理时会强制释放的端口（留空表示仅 port）
+    pub managed_ports: Vec<u16>,
+    /// 可供后端使用的端口池，非空时取代 port：优先沿用上次的端口，否则取第一个空闲端口
+    pub port_pool: Vec<u16>,
+    /// 是否定期上报后端进程的 CPU/内存占用（默认 false）
+    pub resource_monitor: bool,
+    /// 是否监听配置文件变化并自动重新加载（默认 false）
+    pub watch_config: bool,
+    /// 配置文件变化后是否同时重启后端（默认 false，仅在 watch_config 启用时生效）
+    pub restart_on_config_change: bool,
+    /// 资源采样间隔（毫秒，默认 2000）
+    pub resource_monitor_interval_ms: u64,
+    /// 无活动多久后自动停止后端（毫秒，默认不启用），下次 note_backend_activity 时自动拉起
+    pub idle_timeout_ms: Option<u64>,
+    /// 心跳事件间隔（毫秒，默认 0 表示不发送）
+    pub heartbeat_interval_ms: u64,
+    /// 在 127.0.0.1 上以 Prometheus 文本格式提供后端指标的端口（默认不启用）
+    pub metrics_port: Option<u16>,
+    /// set_backend_workers 允许设置的最大工作进程/线程数（默认 32）
+    pub max_backend_workers: u32,
+}
+
+/// 预热请求配置
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WarmupConfig {
+    /// HTTP 方法（默认 "GET"）
+    #[serde(default = "default_warmup_method")]
+    pub method: String,
+    /// 请求路径，如 "/v1/warmup"
+    pub path: String,
+    /// JSON 请求体（可选）
+    #[serde(default)]
+    pub body: Option<serde_json::Value>,
+}
+
+fn default_warmup_method() -> String {
+    "GET".to_string()
+}
+
+/// 关闭进程的一个步骤：发送信号后最多等待 wait_ms
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct KillStep {
+    /// "SIGTERM" | "SIGINT" | "SIGKILL" | "stdin:<要写入的命令>"
+    pub signal: String,
+    /// 发送后等待进程退出的时间（毫秒）
+    pub wait_ms: u64,
+}
+
+/// 解析后的关闭动作
+#[derive(Debug, Clone, PartialEq)]
+enum KillAction {
+    /// 向 stdin 写入一行命令
+    Stdin(String),
+    /// Unix: SIGTERM；Windows 不支持，跳过
+    Term,
+    /// Unix: SIGINT；Windows 不支持，跳过
+    Interrupt,
+    /// 强制结束
+    Kill,
+}
+
+impl KillStep {
+    fn action(&self) -> Result<KillAction, String> {
+        if let Some(command) = self.signal.strip_prefix("stdin:") {
+            return Ok(KillAction::Stdin(command.to_string()));
+        }
+        match self.signal.as_str() {
+            "SIGTERM" => Ok(KillAction::Term),
+            "SIGINT" => Ok(KillAction::Interrupt),
+            "SIGKILL" => Ok(KillAction::Kill),
+            other => Err(format!(
+                "unknown kill_sequence signal '{}', expected SIGTERM|SIGINT|SIGKILL|stdin:<command>",
+                other
+            )),
+        }
+    }
+}
+
+/// 启动就绪判定方式
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadinessStrategy {
+    /// 后端输出中出现指定文本（读取日志文件，仅 Windows；其他平台 validate 会拒绝）
+    StdoutMarker(String),
+    /// 轮询 HTTP 路径直到返回成功（path 默认为 readiness_path / health_path）
+    HttpPoll {
+        #[serde(default)]
+        path: Option<String>,
+    },
+    /// 端口可以建立 TCP 连接
+    TcpConnect,
+    /// 进程启动后立即视为就绪
+    None,
+}
+
+/// 后端进程的环境变量来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EnvMode {
+    /// 继承应用的全部环境变量
+    Inherit,
+    /// 只保留 PATH、HOME 等基本变量，避免残留的 PYTHONPATH 等影响后端
+    Clean,
 }
 
 impl Default for PythonConfig {
@@ -199,43 +475,562 @@ impl Default for PythonConfig {
         Self {
             python_path: "python".to_string(),
             port: 8009,
+            auto_port: true,
             host: "127.0.0.1".to_string(),
+            auth_token: None,
+            allow_external_bind: false,
             auto_restart: true,
+            auto_start: true,
+            splash: false,
+            detached: false,
             startup_timeout_ms: 10000,
+            spawn_confirm_ms: 500,
             dev_mode: false,
+            graceful_shutdown_timeout_ms: 3000,
+            post_kill_timeout_ms: 2000,
+            kill_sequence: None,
+            warmup: None,
+            launcher: None,
+            venv_path: None,
+            env_mode: EnvMode::Inherit,
+            env: BTreeMap::new(),
+            module: "aestiv".to_string(),
+            package_spec: "-e ../src-python".to_string(),
+            health_path: "/health".to_string(),
+            readiness_path: None,
+            readiness: ReadinessStrategy::TcpConnect,
+            docs_path: "/docs".to_string(),
+            openapi_path: "/openapi.json".to_string(),
+            drain_path: None,
+            merge_configs: false,
+            managed_ports: Vec::new(),
+            port_pool: Vec::new(),
+            resource_monitor: false,
+            resource_monitor_interval_ms: 2000,
+            watch_config: false,
+            restart_on_config_change: false,
+            heartbeat_interval_ms: 0,
+            metrics_port: None,
+            max_backend_workers: 32,
+            idle_timeout_ms: None,
+        }
+    }
+}
+
+/// 配置加载警告（未知字段、解析失败等）
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigWarning {
+    /// 配置文件路径
+    pub path: String,
+    /// 未识别的字段（可能来自新版本或拼写错误）
+    pub unknown_keys: Vec<String>,
+    /// 解析错误，存在时表示该文件未被采用
+    pub error: Option<String>,
+    /// 同时存在多个配置文件且未合并时，实际生效的文件
+    pub shadowed_by: Option<String>,
+}
+
+/// 配置加载结果
+#[derive(Debug, Clone)]
+pub struct LoadedConfig {
+    /// 生效的配置
+    pub config: PythonConfig,
+    /// 配置来源文件（None 表示使用默认值）
+    pub path: Option<String>,
+    /// 生效的 profile 名（扁平配置为 None）
+    pub profile: Option<String>,
+    /// 加载过程中的警告
+    pub warnings: Vec<ConfigWarning>,
+    /// 配置文件中写的 python_path（config.python_path 是解析后的实际解释器）
+    pub configured_python_path: String,
+    /// 参与生效配置的文件及其原始内容，按合并顺序（后面的覆盖前面的）
+    pub layers: Vec<(String, serde_json::Value)>,
+}
+
+/// 环境变量覆盖的前缀，变量名为前缀加大写字段名（如 AESTIVUS_PORT=9000）
+const CONFIG_ENV_PREFIX: &str = "AESTIVUS_";
+
+/// 读取环境变量中的配置覆盖；值按 JSON 解析，失败时当作字符串
+fn config_env_overrides() -> serde_json::Map<String, serde_json::Value> {
+    PythonConfig::known_fields()
+        .into_iter()
+        .filter_map(|field| {
+            let raw = std::env::var(format!("{}{}", CONFIG_ENV_PREFIX, field.to_uppercase())).ok()?;
+            let value = serde_json::from_str(&raw).unwrap_or(serde_json::Value::String(raw));
+            Some((field, value))
+        })
+        .collect()
+}
+
+/// 配置文件中某个字段的值：profiles 格式取指定 profile，扁平格式取顶层
+fn layer_field<'a>(document: &'a serde_json::Value, profile: Option<&str>, field: &str) -> Option<&'a serde_json::Value> {
+    match (document.get("profiles"), profile) {
+        (Some(profiles), Some(profile)) => profiles.get(profile)?.get(field),
+        (Some(_), None) => None,
+        (None, _) => document.get(field),
+    }
+}
+
+/// 配置文件搜索路径（按优先级，同目录下 TOML 优先于 JSON）
+const CONFIG_PATHS: [&str; 4] = [
+    "config/python.toml",
+    "config/python.json",
+    "../config/python.toml",
+    "../config/python.json",
+];
+
+/// 尚无配置文件时保存的默认位置
+const DEFAULT_CONFIG_PATH: &str = "config/python.json";
+
+/// 是否为 TOML 配置文件（按扩展名判断）
+fn is_toml_path(path: &str) -> bool {
+    path.ends_with(".toml")
+}
+
+/// 按扩展名解析配置文件内容，统一转换为 JSON 值
+fn parse_config_document(path: &str, content: &str) -> Result<serde_json::Value, String> {
+    if is_toml_path(path) {
+        toml::from_str::<serde_json::Value>(content).map_err(|e| e.to_string())
+    } else {
+        serde_json::from_str::<serde_json::Value>(content).map_err(|e| e.to_string())
+    }
+}
+
+/// 按扩展名序列化配置文件内容
+fn serialize_config_document(path: &str, value: &serde_json::Value) -> Result<String, String> {
+    if is_toml_path(path) {
+        // TOML 没有 null，省略的字段由 serde default 补全
+        toml::to_string_pretty(&strip_nulls(value.clone())).map_err(|e| e.to_string())
+    } else {
+        serde_json::to_string_pretty(value).map_err(|e| e.to_string())
+    }
+}
+
+/// 递归合并配置：对象逐字段合并，其他值由 `overlay` 覆盖
+fn merge_config_values(base: &mut serde_json::Value, overlay: &serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                merge_config_values(base.entry(key.clone()).or_insert(serde_json::Value::Null), value);
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
+/// 递归移除对象中的 null 字段
+fn strip_nulls(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k, strip_nulls(v)))
+                .collect(),
+        ),
+        serde_json::Value::Array(items) => {
+            serde_json::Value::Array(items.into_iter().map(strip_nulls).collect())
         }
+        other => other,
     }
 }
 
+/// 从配置文件内容中选出生效的配置对象
+/// profiles 结构取指定（或 active 指定）的 profile，扁平结构本身即唯一的配置
+/// 返回配置对象、profile 名及顶层未识别的字段
+fn select_profile_document(
+    value: serde_json::Value,
+    profile: Option<&str>,
+) -> Result<(serde_json::Value, Option<String>, Vec<String>), String> {
+    let selected = match value.get("profiles") {
+        Some(profiles) => {
+            let profiles = profiles.as_object().ok_or("\"profiles\" must be an object")?;
+            let name = match profile {
+                Some(name) => name.to_string(),
+                None => value
+                    .get("active")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string())
+                    .or_else(|| profiles.keys().next().cloned())
+                    .ok_or("No profiles defined")?,
+            };
+            let selected = profiles
+                .get(&name)
+                .cloned()
+                .ok_or_else(|| format!("Profile '{}' not found", name))?;
+            // 顶层只允许 active 和 profiles
+            let top_level_unknown = value
+                .as_object()
+                .map(|obj| {
+                    obj.keys()
+                        .filter(|k| k.as_str() != "active" && k.as_str() != "profiles")
+                        .cloned()
+                        .collect()
+                })
+                .unwrap_or_default();
+            (selected, Some(name), top_level_unknown)
+        }
+        None => (value, None, Vec::new()),
+    };
+    Ok(selected)
+}
+
 impl PythonConfig {
     /// 从配置文件加载，如果不存在则使用默认值
     pub fn load() -> Self {
-        let config_paths = vec![
-            "config/python.json",
-            "../config/python.json",
-        ];
+        Self::load_detailed(None).config
+    }
+    
+    /// 从配置文件加载，同时返回来源、profile 和警告
+    /// `profile` 为 None 时使用配置文件中 `active` 指定的 profile
+    pub fn load_detailed(profile: Option<&str>) -> LoadedConfig {
+        let mut warnings = Vec::new();
         
-        for path in config_paths {
-            if let Ok(content) = std::fs::read_to_string(path) {
-                if let Ok(mut config) = serde_json::from_str::<PythonConfig>(&content) {
-                    println!("[tauri] Loaded Python config from {}", path);
-                    if config.python_path == "python" {
-                        config.python_path = detect_python_path();
-                    }
-                    return config;
+        // 收集所有存在且可用的配置文件（按 CONFIG_PATHS 顺序）
+        let mut documents: Vec<(&str, serde_json::Value, Self, Option<String>)> = Vec::new();
+        for path in CONFIG_PATHS {
+            let content = match std::fs::read_to_string(path) {
+                Ok(content) => content,
+                Err(_) => continue,
+            };
+            
+            match parse_config_document(path, &content)
+                .and_then(|value| Self::from_document(value.clone(), profile).map(|(config, name, _)| (value, config, name)))
+            {
+                Ok((value, config, name)) => documents.push((path, value, config, name)),
+                Err(e) => {
+                    println!("[tauri] Warning: failed to parse {}: {}", path, e);
+                    warnings.push(ConfigWarning {
+                        path: path.to_string(),
+                        unknown_keys: Vec::new(),
+                        error: Some(e),
+                        shadowed_by: None,
+                    });
+                }
+            }
+        }
+        
+        if documents.is_empty() {
+            println!("[tauri] Using default Python config");
+            let config = Self::default();
+            let configured_python_path = config.python_path.clone();
+            let mut config = config.apply_env_overrides(&mut warnings);
+            config.resolve_python_path();
+            return LoadedConfig {
+                config,
+                path: None,
+                profile: None,
+                warnings,
+                configured_python_path,
+                layers: Vec::new(),
+            };
+        }
+        
+        // 任一文件开启 merge_configs 时按顺序合并（后面的覆盖前面的），否则第一个文件生效
+        // 合并模式下来源记为第一个文件，保存配置时写回该文件
+        let merge = documents.len() > 1 && documents.iter().any(|(_, _, config, _)| config.merge_configs);
+        let (path, value, layers) = if merge {
+            let paths: Vec<&str> = documents.iter().map(|(path, _, _, _)| *path).collect();
+            println!("[tauri] Merging Python configs: {}", paths.join(" <- "));
+            let mut merged = serde_json::Value::Object(Default::default());
+            for (_, value, _, _) in &documents {
+                merge_config_values(&mut merged, value);
+            }
+            let layers = documents.iter().map(|(path, value, _, _)| (path.to_string(), value.clone())).collect();
+            (documents[0].0, merged, layers)
+        } else {
+            let (winner, value, _, _) = &documents[0];
+            for (ignored, _, _, _) in &documents[1..] {
+                println!("[tauri] Warning: {} is ignored, {} takes precedence", ignored, winner);
+                warnings.push(ConfigWarning {
+                    path: ignored.to_string(),
+                    unknown_keys: Vec::new(),
+                    error: None,
+                    shadowed_by: Some(winner.to_string()),
+                });
+            }
+            (*winner, value.clone(), vec![(winner.to_string(), value.clone())])
+        };
+        
+        match Self::from_document(value, profile) {
+            Ok((config, active_profile, unknown_keys)) => {
+                match &active_profile {
+                    Some(name) => println!("[tauri] Loaded Python config from {} (profile: {})", path, name),
+                    None => println!("[tauri] Loaded Python config from {}", path),
+                }
+                if !unknown_keys.is_empty() {
+                    println!("[tauri] Warning: unknown fields in {}: {}", path, unknown_keys.join(", "));
+                    warnings.push(ConfigWarning {
+                        path: path.to_string(),
+                        unknown_keys,
+                        error: None,
+                        shadowed_by: None,
+                    });
+                }
+                let configured_python_path = config.python_path.clone();
+                let mut config = config.apply_env_overrides(&mut warnings);
+                config.resolve_python_path();
+                LoadedConfig {
+                    config,
+                    path: Some(path.to_string()),
+                    profile: active_profile,
+                    warnings,
+                    configured_python_path,
+                    layers,
+                }
+            }
+            Err(e) => {
+                // 单个文件都已校验通过，只有合并结果可能失败：退回第一个文件
+                println!("[tauri] Warning: merged config is invalid ({}), using {}", e, documents[0].0);
+                warnings.push(ConfigWarning {
+                    path: path.to_string(),
+                    unknown_keys: Vec::new(),
+                    error: Some(format!("merged config is invalid: {}", e)),
+                    shadowed_by: None,
+                });
+                let (path, value, config, active_profile) = documents.swap_remove(0);
+                let configured_python_path = config.python_path.clone();
+                let mut config = config.apply_env_overrides(&mut warnings);
+                config.resolve_python_path();
+                LoadedConfig {
+                    config,
+                    path: Some(path.to_string()),
+                    profile: active_profile,
+                    warnings,
+                    configured_python_path,
+                    layers: vec![(path.to_string(), value)],
                 }
             }
         }
+    }
+    
+    /// 从已解析的配置文件内容中取出配置，返回配置、生效的 profile 名及未识别的字段
+    /// 支持两种结构：
+    /// - 扁平结构：文件本身就是一份配置
+    /// - profiles 结构：`{ "active": "dev", "profiles": { "dev": {...}, "prod": {...} } }`
+    /// 未知字段不会导致失败，只有类型错误或校验失败才会
+    fn from_document(
+        value: serde_json::Value,
+        profile: Option<&str>,
+    ) -> Result<(Self, Option<String>, Vec<String>), String> {
+        let (config_value, active_profile, mut unknown_keys) = select_profile_document(value, profile)?;
         
-        println!("[tauri] Using default Python config");
-        let mut config = Self::default();
-        config.python_path = detect_python_path();
-        config
+        let known = Self::known_fields();
+        if let Some(obj) = config_value.as_object() {
+            unknown_keys.extend(obj.keys().filter(|k| !known.contains(*k)).cloned());
+        }
+        let config = serde_json::from_value::<PythonConfig>(config_value).map_err(|e| e.to_string())?;
+        config.validate()?;
+        Ok((config, active_profile, unknown_keys))
+    }
+    
+    /// 确定实际使用的解释器：venv_path 优先，其次是未显式配置时自动检测
+    fn resolve_python_path(&mut self) {
+        if let Some(venv) = &self.venv_path {
+            self.python_path = venv_python(venv);
+        } else if self.python_path == "python" {
+            self.python_path = detect_python_path();
+        }
+    }
+    
+    /// 校验字段取值
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.binds_loopback_only() && !self.allow_external_bind {
+            return Err(format!(
+                "host '{}' exposes the backend beyond this machine; set allow_external_bind to true to allow it",
+                self.host
+            ));
+        }
+        if !self.health_path.starts_with('/') {
+            return Err(format!("health_path must start with '/', got '{}'", self.health_path));
+        }
+        if let Some(path) = &self.readiness_path {
+            if !path.starts_with('/') {
+                return Err(format!("readiness_path must start with '/', got '{}'", path));
+            }
+        }
+        if !self.docs_path.starts_with('/') {
+            return Err(format!("docs_path must start with '/', got '{}'", self.docs_path));
+        }
+        if !self.openapi_path.starts_with('/') {
+            return Err(format!("openapi_path must start with '/', got '{}'", self.openapi_path));
+        }
+        if !is_python_module_name(&self.module) {
+            return Err(format!("module must be a dotted Python module name, got '{}'", self.module));
+        }
+        if self.package_spec.trim().is_empty() {
+            return Err("package_spec must not be empty, e.g. \"-e ../src-python\" or \"aestiv\"".to_string());
+        }
+        if let Some(launcher) = &self.launcher {
+            if launcher.first().map(|program| program.trim().is_empty()).unwrap_or(true) {
+                return Err("launcher must name a program, e.g. [\"uv\", \"run\"]".to_string());
+            }
+        }
+        for step in self.kill_sequence.iter().flatten() {
+            step.action()?;
+        }
+        if let Some(venv) = &self.venv_path {
+            if !std::path::Path::new(&venv_python(venv)).exists() {
+                return Err(format!("venv_path '{}' does not contain a Python interpreter", venv.display()));
+            }
+        }
+        match &self.readiness {
+            ReadinessStrategy::StdoutMarker(marker) if marker.is_empty() => {
+                return Err("readiness stdout_marker must not be empty".to_string());
+            }
+            // 其他平台的后端经终端启动，输出不经过日志文件，无从匹配
+            #[cfg(not(target_os = "windows"))]
+            ReadinessStrategy::StdoutMarker(_) => {
+                return Err("readiness stdout_marker is only supported on Windows, use tcp_connect or http_poll".to_string());
+            }
+            ReadinessStrategy::HttpPoll { path: Some(path) } if !path.starts_with('/') => {
+                return Err(format!("readiness http_poll path must start with '/', got '{}'", path));
+            }
+            _ => {}
+        }
+        if let Some(path) = &self.drain_path {
+            if !path.starts_with('/') {
+                return Err(format!("drain_path must start with '/', got '{}'", path));
+            }
+        }
+        if let Some(warmup) = &self.warmup {
+            if !warmup.path.starts_with('/') {
+                return Err(format!("warmup.path must start with '/', got '{}'", warmup.path));
+            }
+        }
+        Ok(())
+    }
+    
+    /// 本机连接后端时使用的地址：监听所有地址（0.0.0.0 / ::）时连接对应的回环地址
+    /// 端口检查、健康检查、就绪探测和后端请求都经过这里
+    pub fn connect_host(&self) -> &str {
+        match self.host.trim_start_matches('[').trim_end_matches(']') {
+            "" | "0.0.0.0" => "127.0.0.1",
+            "::" => "::1",
+            host => host,
+        }
+    }
+    
+    /// 本机访问后端的 HTTP 基础地址，IPv6 地址加方括号
+    pub fn connect_base_url(&self, port: u16) -> String {
+        let host = self.connect_host();
+        if host.contains(':') {
+            format!("http://[{}]:{}", host, port)
+        } else {
+            format!("http://{}:{}", host, port)
+        }
+    }
+    
+    /// 监听地址是否仅限本机回环
+    pub fn binds_loopback_only(&self) -> bool {
+        self.host.eq_ignore_ascii_case("localhost")
+            || self.host.parse::<std::net::IpAddr>().map(|ip| ip.is_loopback()).unwrap_or(false)
+    }
+    
+    /// 清理时需要释放的端口
+    pub fn managed_port_set(&self) -> Vec<u16> {
+        if self.managed_ports.is_empty() {
+            vec![self.port]
+        } else {
+            self.managed_ports.clone()
+        }
+    }
+    
+    /// 关闭后端时需要释放的端口：配置了端口池时只清理当前使用的端口
+    pub fn cleanup_port_set(&self, active_port: u16) -> Vec<u16> {
+        if self.port_pool.is_empty() {
+            self.managed_port_set()
+        } else {
+            vec![active_port]
+        }
+    }
+    
+    /// 启动时首选的端口：配置了端口池时优先沿用上次固定的端口
+    fn preferred_port(&self) -> u16 {
+        match PinnedPort::load() {
+            Some(pinned) if self.port_pool.contains(&pinned.port) => pinned.port,
+            _ => self.port_pool.first().copied().unwrap_or(self.port),
+        }
+    }
+    
+    /// 实际执行的关闭步骤：未配置 kill_sequence 时按超时配置生成默认步骤
+    pub fn effective_kill_sequence(&self) -> Vec<KillStep> {
+        if let Some(steps) = &self.kill_sequence {
+            return steps.clone();
+        }
+        vec![
+            KillStep { signal: "stdin:sidecar shutdown".to_string(), wait_ms: self.graceful_shutdown_timeout_ms },
+            KillStep { signal: "SIGTERM".to_string(), wait_ms: self.graceful_shutdown_timeout_ms },
+            KillStep { signal: "SIGKILL".to_string(), wait_ms: self.post_kill_timeout_ms },
+        ]
+    }
+    
+    /// 启动就绪检查使用的路径
+    pub fn effective_readiness_path(&self) -> &str {
+        self.readiness_path.as_deref().unwrap_or(&self.health_path)
+    }
+    
+    /// 在配置文件之上应用环境变量覆盖；覆盖后配置无效时忽略全部覆盖并记录警告
+    fn apply_env_overrides(self, warnings: &mut Vec<ConfigWarning>) -> Self {
+        let overrides = config_env_overrides();
+        if overrides.is_empty() {
+            return self;
+        }
+        let keys: Vec<String> = overrides.keys().cloned().collect();
+        let mut value = match serde_json::to_value(&self) {
+            Ok(value) => value,
+            Err(_) => return self,
+        };
+        merge_config_values(&mut value, &serde_json::Value::Object(overrides));
+        let overridden = serde_json::from_value::<Self>(value)
+            .map_err(|e| e.to_string())
+            .and_then(|config| config.validate().map(|_| config));
+        match overridden {
+            Ok(config) => {
+                println!("[tauri] Python config overridden by environment: {}", keys.join(", "));
+                config
+            }
+            Err(e) => {
+                println!("[tauri] Warning: ignoring environment overrides ({}): {}", keys.join(", "), e);
+                warnings.push(ConfigWarning {
+                    path: "environment".to_string(),
+                    unknown_keys: Vec::new(),
+                    error: Some(e),
+                    shadowed_by: None,
+                });
+                self
+            }
+        }
+    }
+    
+    /// 所有已知字段名（由默认配置序列化得到，随结构体自动同步）
+    fn known_fields() -> Vec<String> {
+        serde_json::to_value(Self::default())
+            .ok()
+            .and_then(|v| v.as_object().map(|obj| obj.keys().cloned().collect()))
+            .unwrap_or_default()
+    }
+    
+    /// 配置文件的 JSON Schema（由结构体定义生成，字段说明取自文档注释）
+    pub fn json_schema() -> serde_json::Value {
+        serde_json::to_value(schemars::schema_for!(PythonConfig)).unwrap_or_default()
     }
 }
 
-/// 检测可用的 Python 解释器路径
-fn detect_python_path() -> String {
+/// Python 候选解释器信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PythonCandidate {
+    /// 解释器路径
+    pub path: String,
+    /// `--version` 输出（不可用时为 None）
+    pub version: Option<String>,
+    /// 是否可以运行
+    pub available: bool,
+    /// 是否已安装 aestiv 包
+    pub aestiv_installed: bool,
+}
+
+/// 按优先级排列的 Python 候选路径
+fn python_candidates() -> Vec<&'static str> {
     #[cfg(target_os = "windows")]
     let candidates = vec![
         "python",
@@ -252,17 +1047,146 @@ fn detect_python_path() -> String {
         ".venv/bin/python",
     ];
     
-    for candidate in candidates {
-        let result = Command::new(candidate)
-            .args(["--version"])
-            .output();
-        
-        if let Ok(output) = result {
-            if output.status.success() {
-                let version = String::from_utf8_lossy(&output.stdout);
-                println!("[tauri] Found Python at '{}': {}", candidate, version.trim());
-                return candidate.to_string();
+    candidates
+}
+
+/// 构造运行解释器的命令：有 launcher 时为 `launcher... python_path`
+/// （在后端目录执行，uv/poetry 据此找到项目环境）
+fn python_command(launcher: &[String], python_path: &str) -> Command {
+    let mut cmd = match launcher.split_first() {
+        Some((program, rest)) => {
+            let mut cmd = Command::new(program);
+            cmd.args(rest).arg(python_path).current_dir("../src-python");
+            cmd
+        }
+        None => Command::new(python_path),
+    };
+    cmd.envs(venv_env(python_path));
+    cmd
+}
+
+/// 虚拟环境中解释器的路径
+fn venv_python(venv: &std::path::Path) -> String {
+    let python = if cfg!(target_os = "windows") {
+        venv.join("Scripts").join("python.exe")
+    } else {
+        venv.join("bin").join("python")
+    };
+    python.to_string_lossy().into_owned()
+}
+
+/// 解释器位于虚拟环境中时，返回激活该环境所需的环境变量（VIRTUAL_ENV 与前置了 Scripts/bin 的 PATH）
+///
+/// 部分原生扩展依赖激活后的 PATH 才能加载，仅直接调用 venv 中的 python 不够。
+fn venv_env(python_path: &str) -> Vec<(String, String)> {
+    let Some(bin_dir) = std::path::Path::new(python_path).parent() else {
+        return Vec::new();
+    };
+    let Some(venv) = bin_dir.parent().filter(|venv| venv.join("pyvenv.cfg").is_file()) else {
+        return Vec::new();
+    };
+    
+    let mut paths = vec![bin_dir.to_path_buf()];
+    if let Some(existing) = std::env::var_os("PATH") {
+        paths.extend(std::env::split_paths(&existing));
+    }
+    let path = std::env::join_paths(paths)
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    vec![
+        ("VIRTUAL_ENV".to_string(), venv.to_string_lossy().into_owned()),
+        ("PATH".to_string(), path),
+    ]
+}
+
+/// 探测解释器版本，无法运行时返回 None
+fn probe_python_version(launcher: &[String], python_path: &str) -> Option<String> {
+    let output = python_command(launcher, python_path)
+        .args(["--version"])
+        .output()
+        .ok()?;
+    
+    if !output.status.success() {
+        return None;
+    }
+    
+    // 旧版 Python 把版本号输出到 stderr
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if stdout.is_empty() {
+        Some(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    } else {
+        Some(stdout)
+    }
+}
+
+/// 把配置的解释器路径解析为实际可执行的绝对路径
+///
+/// 相对路径（如 `.venv/bin/python`）在打包安装后工作目录不是项目根目录时会失效：
+/// 依次尝试当前目录、应用资源目录、可执行文件所在目录；都不存在时回退到 PATH 中的 python3/python。
+/// 裸命令名（如 "python3"）按 PATH 查找。找不到时原样返回，由后续检查报告错误。
+fn resolve_python_executable(app_handle: &tauri::AppHandle, python_path: &str) -> String {
+    let path = std::path::Path::new(python_path);
+    let is_bare_name = path.components().count() == 1;
+    
+    let resolved = if path.is_absolute() {
+        Some(path.to_path_buf())
+    } else if is_bare_name {
+        which::which(python_path).ok()
+    } else {
+        let mut bases = Vec::new();
+        if let Ok(cwd) = std::env::current_dir() {
+            bases.push(cwd);
+        }
+        if let Ok(resource_dir) = app_handle.path().resource_dir() {
+            bases.push(resource_dir);
+        }
+        if let Some(exe_dir) = std::env::current_exe().ok().and_then(|exe| exe.parent().map(|p| p.to_path_buf())) {
+            bases.push(exe_dir);
+        }
+        bases.into_iter().map(|base| base.join(path)).find(|candidate| candidate.is_file()).or_else(|| {
+            let fallback = ["python3", "python"].into_iter().find_map(|name| which::which(name).ok());
+            if let Some(fallback) = &fallback {
+                println!("[tauri] '{}' not found, falling back to {} from PATH", python_path, fallback.display());
             }
+            fallback
+        })
+    };
+    
+    match resolved {
+        Some(resolved) => {
+            let resolved = resolved.to_string_lossy().into_owned();
+            println!("[tauri] Using Python interpreter: {}", resolved);
+            resolved
+        }
+        None => python_path.to_string(),
+    }
+}
+
+/// python_path 误配为目录（常见于填了 venv 目录）时改用其中的解释器
+///
+/// 目录中找不到解释器时返回错误，提示应填写的可执行文件路径；不是目录时原样返回。
+fn interpreter_for_path(python_path: &str) -> Result<String, String> {
+    let path = std::path::Path::new(python_path);
+    if !path.is_dir() {
+        return Ok(python_path.to_string());
+    }
+    let candidate = venv_python(path);
+    if std::path::Path::new(&candidate).is_file() {
+        println!("[tauri] python_path '{}' is a directory, using {}", python_path, candidate);
+        return Ok(candidate);
+    }
+    Err(format!(
+        "python_path '{}' is a directory, not a Python executable. Point it at the interpreter itself, e.g. '{}'.",
+        python_path, candidate
+    ))
+}
+
+/// 检测可用的 Python 解释器路径
+fn detect_python_path() -> String {
+    for candidate in python_candidates() {
+        if let Some(version) = probe_python_version(&[], candidate) {
+            println!("[tauri] Found Python at '{}': {}", candidate, version);
+            return candidate.to_string();
         }
     }
     
@@ -270,26 +1194,119 @@ fn detect_python_path() -> String {
     "python".to_string()
 }
 
-/// 检查 aestiv 包是否已安装
-fn check_aestiv_installed(python_path: &str) -> bool {
-    let result = Command::new(python_path)
-        .args(["-c", "import aestiv; print('ok')"])
-        .output();
+/// 从候选列表中找下一个可用且已安装后端模块的解释器（跳过 `exclude` 中的路径）
+fn next_python_candidate(exclude: &[String], module: &str) -> Option<String> {
+    python_candidates()
+        .into_iter()
+        .filter(|candidate| !exclude.iter().any(|p| p == candidate))
+        .find(|candidate| is_python_available(&[], candidate) && check_aestiv_installed(candidate, module))
+        .map(|candidate| candidate.to_string())
+}
+
+/// 是否为合法的点分模块名（如 "aestiv" 或 "mypkg.server"）
+///
+/// 模块名会被拼进 `python -c` 的代码中，只允许标识符字符，避免引号或分号注入代码。
+fn is_python_module_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.split('.').all(|part| {
+            let mut chars = part.chars();
+            chars.next().is_some_and(|c| c == '_' || c.is_alphabetic())
+                && chars.all(|c| c == '_' || c.is_alphanumeric())
+        })
+}
+
+/// 后端包检查结果
+#[derive(Debug, Clone, PartialEq)]
+enum PackageStatus {
+    /// 导入成功
+    Installed,
+    /// 找不到后端模块本身
+    NotInstalled,
+    /// 模块存在，但导入时失败（通常是缺少其他依赖），附带底层错误
+    Broken(String),
+}
+
+/// 尝试导入后端模块，并根据 stderr 区分"未安装"与"已安装但损坏"
+fn check_aestiv_package(launcher: &[String], python_path: &str, module: &str) -> PackageStatus {
+    let output = match python_command(launcher, python_path)
+        .args(["-c", &format!("import {}; print('ok')", module)])
+        .output()
+    {
+        Ok(output) => output,
+        Err(_) => {
+            println!("[tauri] {} package is NOT installed", module);
+            return PackageStatus::NotInstalled;
+        }
+    };
     
-    if let Ok(output) = result {
-        if output.status.success() {
-            println!("[tauri] aestiv package is installed");
-            return true;
+    if output.status.success() {
+        println!("[tauri] {} package is installed", module);
+        return PackageStatus::Installed;
+    }
+    
+    let status = classify_import_error(module, &String::from_utf8_lossy(&output.stderr));
+    match &status {
+        PackageStatus::Broken(error_line) => {
+            println!("[tauri] {} package is installed but failed to import: {}", module, error_line)
         }
+        _ => println!("[tauri] {} package is NOT installed", module),
     }
+    status
+}
+
+/// 根据导入失败时的 stderr 区分"未安装"与"已安装但损坏"
+fn classify_import_error(module: &str, stderr: &str) -> PackageStatus {
+    // traceback 的最后一行是异常本身，如 "ModuleNotFoundError: No module named 'numpy'"
+    let error_line = stderr
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or("")
+        .trim()
+        .to_string();
     
-    println!("[tauri] aestiv package is NOT installed");
-    false
+    // 包名可能带点（如 "mypkg.server"），顶层包缺失时报的是顶层名
+    let top_level = module.split('.').next().unwrap_or(module);
+    let module_missing = [module, top_level].iter().any(|name| {
+        error_line.contains(&format!("No module named '{}'", name))
+            || error_line.ends_with(&format!("No module named {}", name))
+    });
+    if module_missing || error_line.is_empty() {
+        PackageStatus::NotInstalled
+    } else {
+        PackageStatus::Broken(error_line)
+    }
 }
 
+/// 检查后端模块是否已安装且可导入
+fn check_aestiv_installed(python_path: &str, module: &str) -> bool {
+    check_aestiv_package(&[], python_path, module) == PackageStatus::Installed
+}
+
+/// 单个模块的导入检查结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ImportResult {
+    module: String,
+    ok: bool,
+    error: Option<String>,
+}
+
+/// 在一个子进程中逐个导入模块，互不影响（模块名通过 argv 传入，不拼接进代码）
+const IMPORT_CHECK_SCRIPT: &str = r#"
+import importlib, json, sys
+results = []
+for name in sys.argv[1:]:
+    try:
+        importlib.import_module(name)
+        results.append({"module": name, "ok": True, "error": None})
+    except BaseException as e:
+        results.append({"module": name, "ok": False, "error": f"{type(e).__name__}: {e}"})
+print(json.dumps(results))
+"#;
+
 /// 检查 Python 是否可用
-fn is_python_available(python_path: &str) -> bool {
-    Command::new(python_path)
+fn is_python_available(launcher: &[String], python_path: &str) -> bool {
+    python_command(launcher, python_path)
         .args(["--version"])
         .output()
         .map(|o| o.status.success())
@@ -298,17 +1315,180 @@ fn is_python_available(python_path: &str) -> bool {
 
 // ============== Python 进程管理 ==============
 
-/// Python 后端进程包装器
-struct PythonProcess {
-    process: Option<Child>,
-    config: PythonConfig,
-    is_primary: bool,        // 是否是主实例
-    owns_backend: bool,      // 是否拥有后端进程（自己启动的）
-    actual_port: u16,        // 实际使用的端口
+/// detached 模式下留在后台运行的后端，记录在状态文件中供下次启动时接管
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DetachedBackend {
+    pid: u32,
+    port: u16,
 }
 
-impl PythonProcess {
-    fn new(config: PythonConfig) -> Self {
+impl DetachedBackend {
+    /// 获取状态文件路径
+    fn get_state_path() -> PathBuf {
+        let app_data = dirs::data_local_dir()
+            .unwrap_or_else(|| PathBuf::from("."));
+        let state_dir = app_data.join("aestivus");
+        let _ = fs::create_dir_all(&state_dir);
+        state_dir.join("detached_backend.json")
+    }
+    
+    fn load() -> Option<Self> {
+        let content = fs::read_to_string(Self::get_state_path()).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+    
+    fn save(&self) {
+        if let Ok(content) = serde_json::to_string_pretty(self) {
+            let _ = fs::write(Self::get_state_path(), content);
+        }
+    }
+    
+    fn clear() {
+        let _ = fs::remove_file(Self::get_state_path());
+    }
+}
+
+/// 从端口池中选定的端口，持久化后下次启动优先沿用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PinnedPort {
+    port: u16,
+}
+
+impl PinnedPort {
+    /// 获取状态文件路径
+    fn get_state_path() -> PathBuf {
+        let app_data = dirs::data_local_dir()
+            .unwrap_or_else(|| PathBuf::from("."));
+        let state_dir = app_data.join("aestivus");
+        let _ = fs::create_dir_all(&state_dir);
+        state_dir.join("pinned_port.json")
+    }
+    
+    fn load() -> Option<Self> {
+        let content = fs::read_to_string(Self::get_state_path()).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+    
+    fn save(&self) {
+        if let Ok(content) = serde_json::to_string_pretty(self) {
+            let _ = fs::write(Self::get_state_path(), content);
+        }
+    }
+}
+
+/// PID 对应的进程是否仍存在（用于没有 Child 句柄的进程）
+fn pid_alive(pid: u32) -> bool {
+    sysinfo::System::new().refresh_process(sysinfo::Pid::from_u32(pid))
+}
+
+/// Python 后端进程包装器
+struct PythonProcess {
+    process: Option<Child>,
+    config: PythonConfig,
+    is_primary: bool,        // 是否是主实例
+    owns_backend: bool,      // 是否拥有后端进程（自己启动的）
+    actual_port: u16,        // 实际使用的端口
+    config_warnings: Vec<ConfigWarning>, // 加载配置时的警告
+    config_path: Option<String>,         // 配置来源文件
+    auto_restart_override: Option<bool>, // 运行时覆盖 config.auto_restart（None 表示不覆盖）
+    started_at: Option<Instant>,         // 当前进程的启动时间
+    last_activity: Instant,              // 最近一次前端请求后端的时间
+    idle_stopped: bool,                  // 是否因空闲被自动停止
+    lifecycle: VecDeque<LifecycleEvent>, // 进程生命周期历史（最近 LIFECYCLE_HISTORY_LIMIT 条）
+    start_reason: Option<StartReason>,   // 当前进程的启动原因
+    attached_pid: Option<u32>,           // 重新接管的 detached 后端 PID（没有 Child 句柄）
+    state: tokio::sync::watch::Sender<BackendState>, // 后端状态，状态变化时通知等待者
+    startup_progress: Option<StartupProgress>,       // 最近一次启动所处的阶段
+    start_count: u64,                                // 本次运行中启动后端进程的次数
+    last_exit_code: Option<i32>,                     // 最近一次观察到的后端退出码
+    child_is_backend: bool,                          // Child 是否就是 Python 本身（经终端或 launcher 启动时不是）
+    backend_pid: Option<u32>,                        // 实际运行后端的 Python 进程 PID（尚未确定时为 None）
+    persisted_content: Option<String>,               // 应用自己最近一次写入配置文件的内容，配置监听据此忽略自身写入
+    configured_python_path: Option<String>,          // 配置文件中写的 python_path；运行时解析或回退的解释器不写回文件
+}
+
+/// 后端启动阶段（按执行顺序）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum StartupPhase {
+    /// 检查端口占用，决定复用已有服务还是换端口
+    CheckingPort,
+    /// 确认解释器可用（必要时回退到其他候选）
+    DetectingPython,
+    /// 确认后端模块可以导入
+    CheckingPackage,
+    /// 启动进程
+    Spawning,
+    /// 进程已启动，等待就绪检查通过
+    WaitingForReady,
+    Ready,
+    Failed,
+}
+
+/// 当前启动进度
+#[derive(Debug, Clone, Serialize)]
+struct StartupProgress {
+    phase: StartupPhase,
+    detail: Option<String>,
+    /// 进入该阶段的 Unix 时间戳（毫秒）
+    timestamp: u64,
+}
+
+/// 后端运行状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum BackendState {
+    /// 未运行
+    Stopped,
+    /// 进程已启动，尚未就绪
+    Starting,
+    /// 已就绪（包括复用已有服务、接管 detached 后端）
+    Running,
+    /// 正在执行关闭步骤
+    Stopping,
+}
+
+impl BackendState {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "stopped" => Ok(Self::Stopped),
+            "starting" => Ok(Self::Starting),
+            "running" => Ok(Self::Running),
+            "stopping" => Ok(Self::Stopping),
+            other => Err(format!("Unknown backend state '{}', expected stopped|starting|running|stopping", other)),
+        }
+    }
+}
+
+/// 当前后端进程的启动原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum StartReason {
+    /// 应用启动时自动启动
+    Initial,
+    /// 用户（前端命令或引导对话框）手动启动
+    UserCommand,
+    /// 自动拉起（如空闲停止后再次有请求）
+    AutoRestart,
+    /// 配置变更后重启
+    ConfigReload,
+}
+
+/// 保留的生命周期事件条数
+const LIFECYCLE_HISTORY_LIMIT: usize = 200;
+
+/// 后端进程生命周期事件
+#[derive(Debug, Clone, Serialize)]
+struct LifecycleEvent {
+    /// Unix 时间戳（毫秒）
+    timestamp: u64,
+    /// spawn / retry / restart / reuse / reattach / exit / terminate / error / recover
+    kind: &'static str,
+    detail: String,
+}
+
+impl PythonProcess {
+    fn new(config: PythonConfig) -> Self {
         let port = config.port;
         Self { 
             process: None, 
@@ -316,22 +1496,237 @@ impl PythonProcess {
             is_primary: false,
             owns_backend: false,
             actual_port: port,
+            config_warnings: Vec::new(),
+            config_path: None,
+            auto_restart_override: None,
+            started_at: None,
+            last_activity: Instant::now(),
+            idle_stopped: false,
+            lifecycle: VecDeque::new(),
+            start_reason: None,
+            attached_pid: None,
+            state: tokio::sync::watch::channel(BackendState::Stopped).0,
+            startup_progress: None,
+            start_count: 0,
+            last_exit_code: None,
+            child_is_backend: false,
+            backend_pid: None,
+            persisted_content: None,
+            configured_python_path: None,
         }
     }
     
-    fn set_process(&mut self, process: Child) {
+    fn set_config(&mut self, config: PythonConfig) {
+        self.config = config;
+    }
+    
+    fn set_config_path(&mut self, path: Option<String>) {
+        self.config_path = path;
+    }
+    
+    fn config_path(&self) -> Option<&str> {
+        self.config_path.as_deref()
+    }
+    
+    fn set_configured_python_path(&mut self, path: String) {
+        self.configured_python_path = Some(path);
+    }
+    
+    fn set_persisted_content(&mut self, content: String) {
+        self.persisted_content = Some(content);
+    }
+    
+    /// 文件内容是否就是应用自己最近一次写入的内容
+    fn is_persisted_content(&self, content: &str) -> bool {
+        self.persisted_content.as_deref() == Some(content)
+    }
+    
+    fn set_auto_restart_override(&mut self, value: Option<bool>) {
+        self.auto_restart_override = value;
+    }
+    
+    /// 实际生效的自动重启开关（运行时覆盖优先于配置）
+    fn auto_restart(&self) -> bool {
+        self.auto_restart_override.unwrap_or(self.config.auto_restart)
+    }
+    
+    /// 是否已通过 disable_and_stop_python 暂停后端
+    fn is_disabled(&self) -> bool {
+        self.auto_restart_override == Some(false)
+    }
+    
+    fn set_config_warnings(&mut self, warnings: Vec<ConfigWarning>) {
+        self.config_warnings = warnings;
+    }
+    
+    fn config_warnings(&self) -> &[ConfigWarning] {
+        &self.config_warnings
+    }
+    
+    /// `child_is_backend` 为 false 时 Child 只是启动命令，后端 PID 稍后由端口解析
+    fn set_process(&mut self, process: Child, reason: StartReason, child_is_backend: bool) {
+        self.backend_pid = child_is_backend.then(|| process.id());
+        self.child_is_backend = child_is_backend;
         self.process = Some(process);
+        self.attached_pid = None;
+        self.start_reason = Some(reason);
         self.owns_backend = true;
+        self.started_at = Some(Instant::now());
+        self.last_activity = Instant::now();
+        self.idle_stopped = false;
+        self.start_count += 1;
+        self.set_state(BackendState::Starting);
+    }
+    
+    fn start_count(&self) -> u64 {
+        self.start_count
+    }
+    
+    fn set_last_exit_code(&mut self, code: Option<i32>) {
+        self.last_exit_code = code;
+    }
+    
+    fn last_exit_code(&self) -> Option<i32> {
+        self.last_exit_code
     }
     
     fn take_process(&mut self) -> Option<Child> {
+        self.started_at = None;
+        self.start_reason = None;
+        self.attached_pid = None;
+        self.backend_pid = None;
         self.process.take()
     }
     
+    fn set_state(&self, state: BackendState) {
+        self.state.send_if_modified(|current| std::mem::replace(current, state) != state);
+    }
+    
+    fn state(&self) -> BackendState {
+        *self.state.borrow()
+    }
+    
+    fn subscribe_state(&self) -> tokio::sync::watch::Receiver<BackendState> {
+        self.state.subscribe()
+    }
+    
+    fn set_startup_progress(&mut self, progress: StartupProgress) {
+        self.startup_progress = Some(progress);
+    }
+    
+    fn startup_progress(&self) -> Option<StartupProgress> {
+        self.startup_progress.clone()
+    }
+    
+    fn start_reason(&self) -> Option<StartReason> {
+        self.start_reason
+    }
+    
+    /// 当前进程已运行的时间
+    fn uptime(&self) -> Option<Duration> {
+        self.started_at.map(|started| started.elapsed())
+    }
+    
+    fn note_activity(&mut self) {
+        self.last_activity = Instant::now();
+    }
+    
+    fn idle_for(&self) -> Duration {
+        self.last_activity.elapsed()
+    }
+    
+    fn record_lifecycle(&mut self, kind: &'static str, detail: String) {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        if self.lifecycle.len() >= LIFECYCLE_HISTORY_LIMIT {
+            self.lifecycle.pop_front();
+        }
+        self.lifecycle.push_back(LifecycleEvent { timestamp, kind, detail });
+    }
+    
+    /// 最近的 `limit` 条生命周期事件（按时间先后）
+    fn lifecycle(&self, limit: usize) -> Vec<LifecycleEvent> {
+        let skip = self.lifecycle.len().saturating_sub(limit);
+        self.lifecycle.iter().skip(skip).cloned().collect()
+    }
+    
+    fn set_idle_stopped(&mut self, value: bool) {
+        self.idle_stopped = value;
+    }
+    
+    fn idle_stopped(&self) -> bool {
+        self.idle_stopped
+    }
+    
     fn has_process(&self) -> bool {
         self.process.is_some()
     }
     
+    /// 实际运行后端的 Python 进程 PID，信号、资源采样等按 PID 进行的操作都用它
+    ///
+    /// 经终端或 launcher 启动时 Child 只是启动命令，在就绪后按端口确定之前为 None。
+    fn pid(&self) -> Option<u32> {
+        self.backend_pid.or(self.attached_pid)
+    }
+    
+    fn set_backend_pid(&mut self, pid: u32) {
+        self.backend_pid = Some(pid);
+    }
+    
+    fn child_is_backend(&self) -> bool {
+        self.child_is_backend
+    }
+    
+    fn child_mut(&mut self) -> Option<&mut Child> {
+        self.process.as_mut()
+    }
+    
+    /// 进程是否仍在运行（已退出或未启动时返回 false）
+    /// 接管的 detached 后端没有 Child 句柄，按 PID 是否存在判断
+    fn is_running(&mut self) -> bool {
+        let running = match self.process.as_mut() {
+            Some(process) if self.child_is_backend => matches!(process.try_wait(), Ok(None)),
+            // Child 只是终端或 launcher，可能在 Python 启动后立即退出，以实际后端为准
+            Some(process) => {
+                matches!(process.try_wait(), Ok(None))
+                    || match self.backend_pid {
+                        Some(pid) => pid_alive(pid),
+                        // PID 要等就绪后才能确定；启动期间视为存活，之后看端口是否仍被占用
+                        None => {
+                            *self.state.borrow() == BackendState::Starting
+                                || is_port_in_use(self.config.connect_host(), self.actual_port)
+                        }
+                    }
+            }
+            None => match self.attached_pid {
+                Some(pid) => pid_alive(pid),
+                // 复用的外部服务不归我们管理，状态保持不变
+                None => return false,
+            },
+        };
+        // 进程自行退出（崩溃）时在这里首次被发现
+        if !running {
+            self.set_state(BackendState::Stopped);
+        }
+        running
+    }
+    
+    /// 接管上次留在后台运行的后端
+    fn set_attached(&mut self, pid: u32, port: u16) {
+        self.attached_pid = Some(pid);
+        self.actual_port = port;
+        self.owns_backend = false;
+        self.started_at = None;
+        self.start_reason = None;
+        self.set_state(BackendState::Running);
+    }
+    
+    fn is_attached(&self) -> bool {
+        self.attached_pid.is_some()
+    }
+    
     fn config(&self) -> &PythonConfig {
         &self.config
     }
@@ -356,11 +1751,68 @@ impl PythonProcess {
         self.owns_backend
     }
     
+    /// 重置为没有后端进程的状态（保留配置、主实例身份与生命周期历史）
+    ///
+    /// 仍持有的子进程会被结束，因为无法确认它处于什么状态。
+    fn reset(&mut self) {
+        if let Some(mut process) = self.process.take() {
+            println!("[tauri] Killing Python process (PID: {}) during state reset", process.id());
+            let _ = process.kill();
+            let _ = process.wait();
+        }
+        self.owns_backend = false;
+        self.actual_port = self.config.port;
+        self.started_at = None;
+        self.start_reason = None;
+        self.attached_pid = None;
+        self.backend_pid = None;
+        self.idle_stopped = false;
+        self.record_lifecycle("recover", "state reset after lock poisoning".to_string());
+        self.set_state(BackendState::Stopped);
+    }
+    
     fn set_reusing_backend(&mut self) {
         self.owns_backend = false;
+        self.set_state(BackendState::Running);
+    }
+}
+
+/// 锁定进程状态；锁因 panic 中毒时先恢复再返回
+///
+/// 中毒后的数据可能停在任意中间状态，因此会重置为空闲状态（保留配置），
+/// 避免一次 panic 让之后所有后端管理命令都失败。
+fn lock_process(state: &Mutex<PythonProcess>) -> MutexGuard<'_, PythonProcess> {
+    match state.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => {
+            println!("[tauri] Warning: process state lock was poisoned, resetting state");
+            let mut guard = poisoned.into_inner();
+            guard.reset();
+            state.clear_poison();
+            guard
+        }
     }
 }
 
+/// 改变后端生命周期（启动/停止/重启）的操作队列
+///
+/// tokio 的 Mutex 按申请顺序唤醒等待者，这些操作因此互斥并按提交顺序依次执行，
+/// 避免"刚停止就被重启"或两次结束同一个进程之类的竞争。
+#[derive(Default)]
+struct LifecycleQueue(Arc<tokio::sync::Mutex<()>>);
+
+/// 在异步任务中排队，守卫释放前其他生命周期操作都会等待
+async fn lifecycle_turn(app_handle: &tauri::AppHandle) -> Option<tokio::sync::OwnedMutexGuard<()>> {
+    let queue = app_handle.try_state::<LifecycleQueue>()?.0.clone();
+    Some(queue.lock_owned().await)
+}
+
+/// 在普通线程（同步命令、监听线程、对话框回调）中排队，不能在异步任务中调用
+fn lifecycle_turn_blocking(app_handle: &tauri::AppHandle) -> Option<tokio::sync::OwnedMutexGuard<()>> {
+    let queue = app_handle.try_state::<LifecycleQueue>()?.0.clone();
+    Some(queue.blocking_lock_owned())
+}
+
 impl Drop for PythonProcess {
     fn drop(&mut self) {
         // 只有自己启动的后端才需要清理
@@ -379,304 +1831,3738 @@ impl Drop for PythonProcess {
 
 // ============== 进程清理 ==============
 
-fn cleanup_python_process(app_handle: &tauri::AppHandle) {
-    println!("[tauri] Cleaning up Python backend process...");
-    if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
-        if let Ok(mut child) = state.lock() {
-            if let Some(mut process) = child.take_process() {
-                println!("[tauri] Killing Python process...");
-                let _ = process.kill();
-                let _ = process.wait();
-                println!("[tauri] Python process terminated.");
+/// 等待进程退出，超时返回 false
+fn wait_for_exit(process: &mut Child, timeout_ms: u64) -> bool {
+    let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+    while Instant::now() < deadline {
+        match process.try_wait() {
+            Ok(Some(status)) => {
+                println!("[tauri] Python process exited: {}", status);
+                return true;
             }
+            Ok(None) => std::thread::sleep(Duration::from_millis(50)),
+            Err(_) => return false,
         }
     }
-    
-    // 额外清理端口
-    cleanup_python_ports();
+    false
 }
 
-fn cleanup_python_ports() {
-    let ports = [8008, 8009, 8010, 8011, 8012];
-    
-    #[cfg(target_os = "windows")]
-    for port in ports {
-        let _ = Command::new("cmd")
-            .args(["/C", &format!(
-                "for /f \"tokens=5\" %a in ('netstat -aon ^| findstr :{} ^| findstr LISTENING') do taskkill /F /PID %a 2>nul",
-                port
-            )])
-            .output();
-    }
+/// 写入 stdin 关闭命令的最长等待时间
+const STDIN_WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// 通过 stdin 发送 "sidecar shutdown" 请求后端自行退出，返回是否写入成功
+///
+/// 后端不读取 stdin 时管道写满会阻塞，因此在线程中写入并最多等待 STDIN_WRITE_TIMEOUT；
+/// 超时的写线程会在进程被结束、管道断开后退出。
+fn request_stdin_shutdown(process: &mut Child, command: &str) -> bool {
+    use std::io::Write;
     
-    #[cfg(not(target_os = "windows"))]
-    for port in ports {
-        if let Ok(output) = Command::new("lsof").args(["-ti", &format!(":{}", port)]).output() {
-            let pids = String::from_utf8_lossy(&output.stdout);
-            for pid in pids.trim().split('\n').filter(|s| !s.is_empty()) {
-                let _ = Command::new("kill").args(["-9", pid]).output();
-            }
+    let Some(mut stdin) = process.stdin.take() else {
+        println!("[tauri] Python stdin is not piped, skipping stdin shutdown");
+        return false;
+    };
+    let line = format!("{}\n", command);
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        // write_all 会处理部分写入
+        let result = stdin.write_all(line.as_bytes()).and_then(|_| stdin.flush());
+        let _ = tx.send((result, stdin));
+    });
+    match rx.recv_timeout(STDIN_WRITE_TIMEOUT) {
+        Ok((Ok(()), stdin)) => {
+            // 放回句柄，后续的 stdin 步骤还能继续写入
+            process.stdin = Some(stdin);
+            println!("[tauri] Sent '{}' via stdin", command);
+            true
+        }
+        Ok((Err(e), _)) => {
+            println!("[tauri] Failed to write shutdown command to stdin: {}", e);
+            false
+        }
+        Err(_) => {
+            println!("[tauri] Writing shutdown command to stdin timed out after {}ms", STDIN_WRITE_TIMEOUT.as_millis());
+            false
         }
     }
 }
 
-// ============== Tauri 命令 ==============
-
-#[tauri::command]
-fn greet(name: &str) -> String {
-    format!("Hello, {}! You've been greeted from Rust!", name)
-}
-
-#[tauri::command]
-fn toggle_fullscreen(window: tauri::Window) {
-    if let Ok(is_fullscreen) = window.is_fullscreen() {
-        let _ = window.set_fullscreen(!is_fullscreen);
-    }
-}
-
-/// 启动 Python 后端进程（支持多实例）
-fn spawn_python_backend(app_handle: tauri::AppHandle, is_primary: bool) -> Result<u16, String> {
-    let config = if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
-        let mut process_state = state.lock().unwrap();
-        if process_state.has_process() {
-            println!("[tauri] Python backend is already running.");
-            return Ok(process_state.actual_port());
+/// 向进程发送一个关闭动作，返回是否发送成功（不支持或失败时跳过该步骤的等待）
+fn send_kill_action(process: &mut Child, action: &KillAction) -> bool {
+    let pid = process.id();
+    match action {
+        KillAction::Stdin(command) => request_stdin_shutdown(process, command),
+        KillAction::Kill => {
+            println!("[tauri] Killing Python process (PID: {})...", pid);
+            process.kill().is_ok()
         }
-        process_state.set_primary(is_primary);
-        process_state.config().clone()
-    } else {
-        return Err("Failed to access app state".to_string());
-    };
-
-    let default_port = config.port;
-    
-    // 主实例逻辑：检查 8009 是否已有服务
-    if is_primary {
-        if is_port_in_use(default_port) {
-            // 端口被占用，检查是否是 aestivus 服务
-            if check_aestivus_service(default_port) {
-                println!("[tauri] Found existing aestivus service on port {}, reusing...", default_port);
-                if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
-                    let mut process_state = state.lock().unwrap();
-                    process_state.set_actual_port(default_port);
-                    process_state.set_reusing_backend();
-                }
-                let _ = app_handle.emit("python-ready", default_port);
-                return Ok(default_port);
+        #[cfg(unix)]
+        KillAction::Term | KillAction::Interrupt => {
+            let (signal, name) = if *action == KillAction::Term {
+                (libc::SIGTERM, "SIGTERM")
             } else {
-                // 端口被其他程序占用，找新端口
-                println!("[tauri] Port {} occupied by other service, finding new port...", default_port);
+                (libc::SIGINT, "SIGINT")
+            };
+            // SAFETY: kill(2) 只向指定 PID 发送信号，不涉及内存操作
+            let sent = unsafe { libc::kill(pid as libc::pid_t, signal) } == 0;
+            if sent {
+                println!("[tauri] Sent {} to Python process (PID: {})", name, pid);
             }
+            sent
+        }
+        // Windows 上后端以 CREATE_NO_WINDOW 启动，与 GUI 进程不共享控制台，
+        // CTRL_C/CTRL_BREAK 送不到，只能走 stdin 关闭命令
+        #[cfg(not(unix))]
+        _ => {
+            println!("[tauri] {:?} is not supported on this platform, skipping", action);
+            false
         }
     }
-    
-    // 确定要使用的端口
-    let actual_port = if is_primary && !is_port_in_use(default_port) {
-        default_port
-    } else {
-        // 如果是主实例但端口被占用，尝试清理后再检查一次
-        if is_primary {
-            println!("[tauri] Port {} occupied, attempting cleanup...", default_port);
-            cleanup_python_ports();
-            std::thread::sleep(std::time::Duration::from_millis(500));
-            if !is_port_in_use(default_port) {
-                default_port
-            } else {
-                find_available_port(default_port + 1)
+}
+
+/// 结束 Python 进程：按顺序执行关闭步骤，进程退出后立即停止
+/// 默认步骤为 stdin 请求退出 → SIGTERM（让 Python 的信号处理器有机会清理）→ SIGKILL，
+/// Windows 不支持 SIGTERM/SIGINT（控制台事件到不了无控制台的后端），会从 stdin 请求直接跳到强制结束。
+/// 只用于 Child 就是 Python 本身的情况，否则见 terminate_pid。
+/// 所有步骤执行完进程仍在运行、且最后一步不是 SIGKILL 时再强制结束一次，最多等待 post_kill_timeout_ms。
+/// 返回进程的退出状态（仍未退出时为 None）
+fn terminate_process(process: &mut Child, steps: &[KillStep], post_kill_timeout_ms: u64) -> Option<std::process::ExitStatus> {
+    let mut killed = false;
+    for step in steps {
+        let action = match step.action() {
+            Ok(action) => action,
+            Err(e) => {
+                println!("[tauri] Skipping kill step: {}", e);
+                continue;
             }
-        } else {
-            // 多开实例，找可用端口
-            find_available_port(default_port + 1)
+        };
+        if !send_kill_action(process, &action) {
+            continue;
         }
-    };
-    
-    println!("[tauri] Starting Python backend on port {} (primary: {})", actual_port, is_primary);
-    
-    if !is_python_available(&config.python_path) {
-        let msg = format!("Python not found at '{}'.", config.python_path);
-        println!("[tauri] Error: {}", msg);
-        let _ = app_handle.emit("python-error", msg.clone());
-        return Err(msg);
+        killed = action == KillAction::Kill;
+        if wait_for_exit(process, step.wait_ms) {
+            return process.try_wait().ok().flatten();
+        }
+        println!("[tauri] Python process still alive {}ms after {}", step.wait_ms, step.signal);
     }
     
-    if !check_aestiv_installed(&config.python_path) {
-        let msg = "aestiv package not found. Run: pip install -e ./src-python".to_string();
-        println!("[tauri] Error: {}", msg);
-        let _ = app_handle.emit("python-error", msg.clone());
-        return Err(msg);
+    if !killed && !matches!(process.try_wait(), Ok(Some(_))) {
+        println!("[tauri] Kill sequence exhausted, killing Python process...");
+        let _ = process.kill();
+        if !wait_for_exit(process, post_kill_timeout_ms) {
+            println!("[tauri] Python process still alive {}ms after kill", post_kill_timeout_ms);
+        }
     }
-    
-    // 构建启动参数（带端口）
-    let port_str = actual_port.to_string();
-    let mut args = vec!["-m", "aestiv", "--port", &port_str];
-    if config.dev_mode {
-        args.push("--standalone");
+    process.try_wait().ok().flatten()
+}
+
+/// 按 PID 结束没有可用 Child 句柄的后端（接管的 detached 后端、经终端或 launcher 启动的后端）
+///
+/// 按同样的关闭步骤发送信号，stdin 步骤无法执行而跳过；返回进程是否已退出。
+fn terminate_pid(pid: u32, steps: &[KillStep], post_kill_timeout_ms: u64) -> bool {
+    let mut killed = false;
+    for step in steps {
+        let sent = match step.action() {
+            Ok(KillAction::Kill) => {
+                println!("[tauri] Killing Python process (PID: {})...", pid);
+                kill_pid(pid);
+                true
+            }
+            #[cfg(unix)]
+            Ok(action @ (KillAction::Term | KillAction::Interrupt)) => {
+                let signal = if action == KillAction::Term { libc::SIGTERM } else { libc::SIGINT };
+                // SAFETY: kill(2) 只向指定 PID 发送信号，不涉及内存操作
+                unsafe { libc::kill(pid as libc::pid_t, signal) == 0 }
+            }
+            Ok(_) => false,
+            Err(e) => {
+                println!("[tauri] Skipping kill step: {}", e);
+                false
+            }
+        };
+        if !sent {
+            continue;
+        }
+        killed = matches!(step.action(), Ok(KillAction::Kill));
+        if wait_for_pids_gone(&[pid], step.wait_ms) {
+            return true;
+        }
+        println!("[tauri] Python process (PID: {}) still alive {}ms after {}", pid, step.wait_ms, step.signal);
     }
     
-    println!("[tauri] Spawning: {} {:?}", config.python_path, args);
+    // 最后一步已经是 SIGKILL 时不再重复
+    if killed {
+        return false;
+    }
+    println!("[tauri] Kill sequence exhausted, killing Python process (PID: {})...", pid);
+    kill_pid(pid);
+    wait_for_pids_gone(&[pid], post_kill_timeout_ms)
+}
+
+/// 在端口上查找 aestivus 自己的后端进程，用于确定经终端或 launcher 启动的后端 PID
+fn find_backend_pid(port: u16, config: &PythonConfig) -> Option<u32> {
+    port_owners(port, config)
+        .into_iter()
+        .find(|owner| owner.kind == PortOwnerKind::Ours)
+        .map(|owner| owner.pid)
+}
+
+/// 排空阶段单次 HTTP 请求的超时
+const DRAIN_REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 关闭第一阶段：请求后端停止接收新请求，并等待正在处理的请求完成
+///
+/// 轮询健康检查，直到返回 `{"status": "drained"}`、后端不再响应或超时。
+/// 请求走 backend_request（配置的 host 和鉴权令牌），调用时不能持有进程状态锁，也不能在异步任务中调用。
+fn drain_backend(app_handle: &tauri::AppHandle, drain_path: &str, health_path: &str, timeout_ms: u64) -> bool {
+    let request = |method: &'static str, path: &str| {
+        let path = path.to_string();
+        async move {
+            tokio::time::timeout(DRAIN_REQUEST_TIMEOUT, backend_request(app_handle, method, &path, None))
+                .await
+                .unwrap_or_else(|_| Err("request timed out".to_string()))
+        }
+    };
     
-    // Windows: 静默后台启动，日志写入文件
-    #[cfg(target_os = "windows")]
-    let child = {
-        use std::process::Stdio;
+    tauri::async_runtime::block_on(async {
+        match request("POST", drain_path).await {
+            Ok(resp) if resp.is_success() => {
+                println!("[tauri] Drain requested at {}, waiting up to {}ms...", drain_path, timeout_ms);
+            }
+            _ => {
+                println!("[tauri] Drain request to {} failed, skipping drain phase", drain_path);
+                return false;
+            }
+        }
         
-        let log_path = get_python_log_path();
-        println!("[tauri] Python backend log: {:?}", log_path);
+        let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+        while Instant::now() < deadline {
+            let drained = match request("GET", health_path).await {
+                Ok(resp) => resp.json().get("status").and_then(|s| s.as_str()) == Some("drained"),
+                // 后端已不再响应，视为排空完成
+                Err(_) => true,
+            };
+            if drained {
+                println!("[tauri] Backend drained");
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
         
-        // 打开日志文件（追加模式）
-        let log_file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&log_path)
-            .map_err(|e| {
-                let msg = format!("Failed to open log file: {}", e);
-                println!("[tauri] {}", msg);
-                msg
-            })?;
+        println!("[tauri] Backend did not finish draining within {}ms", timeout_ms);
+        false
+    })
+}
+
+/// python-terminated 事件负载
+#[derive(Debug, Clone, Serialize)]
+struct TerminatedPayload {
+    pid: u32,
+    /// 退出码（被信号结束时为 None）
+    code: Option<i32>,
+    /// 结束进程的信号（仅 Unix）
+    signal: Option<i32>,
+    /// 是否由 aestivus 主动关闭
+    requested: bool,
+    /// ExitStatus 的原始描述
+    raw: String,
+}
+
+impl TerminatedPayload {
+    fn new(pid: u32, status: std::process::ExitStatus, requested: bool) -> Self {
+        #[cfg(unix)]
+        let signal = {
+            use std::os::unix::process::ExitStatusExt;
+            status.signal()
+        };
+        #[cfg(not(unix))]
+        let signal = None;
         
-        let log_file_err = log_file.try_clone().map_err(|e| {
-            let msg = format!("Failed to clone log file handle: {}", e);
-            println!("[tauri] {}", msg);
-            msg
-        })?;
+        Self { pid, code: status.code(), signal, requested, raw: status.to_string() }
+    }
+}
+
+fn cleanup_python_process(app_handle: &tauri::AppHandle) {
+    println!("[tauri] Cleaning up Python backend process...");
+    let mut config = PythonConfig::default();
+    let mut port = config.port;
+    if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+        // 排空阶段不持有锁：最长要等 graceful_shutdown_timeout_ms，期间其他命令仍可读取状态
+        let drain = {
+            let mut child = lock_process(&state);
+            let live = (child.has_process() && child.is_running()) || child.is_attached();
+            let config = child.config();
+            config
+                .drain_path
+                .clone()
+                .filter(|_| live)
+                .map(|path| (path, config.health_path.clone(), config.graceful_shutdown_timeout_ms))
+        };
+        if let Some((drain_path, health_path, timeout_ms)) = drain {
+            drain_backend(app_handle, &drain_path, &health_path, timeout_ms);
+        }
         
-        // 静默启动 Python 进程，无控制台窗口
-        // 设置 PYTHONIOENCODING=utf-8 避免 Windows GBK 编码问题
-        Command::new(&config.python_path)
-            .args(&args)
-            .env("PYTHONIOENCODING", "utf-8")
-            .env("PYTHONUTF8", "1")
-            .current_dir("../src-python")
-            .creation_flags(CREATE_NO_WINDOW)
-            .stdout(Stdio::from(log_file))
-            .stderr(Stdio::from(log_file_err))
-            .spawn()
-            .map_err(|e| {
-                let msg = format!("Failed to spawn Python: {}", e);
-                println!("[tauri] {}", msg);
-                let _ = app_handle.emit("python-error", msg.clone());
-                msg
-            })?
+        // 锁内只取出要结束的进程，关闭步骤在锁外执行：kill_sequence 由用户配置，可能持续很久，
+        // 期间状态查询、心跳和资源监控仍能拿到锁（此时状态为 Stopping）
+        let (process, child_is_backend, backend_pid, attached_pid) = {
+            let mut child = lock_process(&state);
+            config = child.config().clone();
+            port = child.actual_port();
+            let attached_pid = if child.is_attached() { child.pid() } else { None };
+            let child_is_backend = child.child_is_backend();
+            let backend_pid = child.pid();
+            let process = child.take_process();
+            if process.is_some() || attached_pid.is_some() {
+                child.set_state(BackendState::Stopping);
+            }
+            (process, child_is_backend, backend_pid, attached_pid)
+        };
+        
+        let steps = config.effective_kill_sequence();
+        let post_kill_timeout_ms = config.post_kill_timeout_ms;
+        let mut lifecycle = None;
+        let mut terminated = None;
+        if let Some(mut process) = process {
+            let pid = process.id();
+            if !child_is_backend {
+                // Child 只是终端/launcher 启动命令，信号要发给真正的后端进程
+                let detail = match backend_pid.or_else(|| find_backend_pid(port, &config)) {
+                    Some(target) => {
+                        println!("[tauri] Stopping Python process (PID: {}) started via launcher PID {}...", target, pid);
+                        if terminate_pid(target, &steps, post_kill_timeout_ms) {
+                            format!("pid {} (launcher {}) stopped", target, pid)
+                        } else {
+                            format!("pid {} (launcher {}) did not exit after kill", target, pid)
+                        }
+                    }
+                    None => format!("launcher pid {} stopped, backend pid unknown", pid),
+                };
+                let _ = process.kill();
+                let _ = process.wait();
+                lifecycle = Some(("terminate", detail));
+            } else if let Ok(Some(status)) = process.try_wait() {
+                // 进程在关闭前已自行退出（崩溃或被外部结束）
+                lifecycle = Some(("exit", format!("pid {} had already exited: {}", pid, status)));
+                terminated = Some(TerminatedPayload::new(pid, status, false));
+            } else {
+                println!("[tauri] Stopping Python process...");
+                let detail = match terminate_process(&mut process, &steps, post_kill_timeout_ms) {
+                    Some(status) => {
+                        terminated = Some(TerminatedPayload::new(pid, status, true));
+                        format!("pid {} stopped: {}", pid, status)
+                    }
+                    None => format!("pid {} did not exit after kill", pid),
+                };
+                lifecycle = Some(("terminate", detail));
+                println!("[tauri] Python process terminated.");
+            }
+        } else if let Some(pid) = attached_pid {
+            // 接管的 detached 后端没有 Child 句柄，只能按 PID 结束
+            println!("[tauri] Stopping reattached Python process (PID: {})...", pid);
+            let detail = if terminate_pid(pid, &steps, post_kill_timeout_ms) {
+                format!("reattached pid {} stopped", pid)
+            } else {
+                format!("reattached pid {} did not exit after kill", pid)
+            };
+            lifecycle = Some(("terminate", detail));
+        }
+        
+        {
+            let mut child = lock_process(&state);
+            if let Some((kind, detail)) = lifecycle {
+                child.record_lifecycle(kind, detail);
+            }
+            if let Some(payload) = &terminated {
+                child.set_last_exit_code(payload.code);
+            }
+            child.set_state(BackendState::Stopped);
+        }
+        if let Some(payload) = terminated {
+            let _ = app_handle.emit(events::TERMINATED, payload);
+        }
+    }
+    
+    // 额外清理端口
+    cleanup_python_ports(&config.cleanup_port_set(port), &config);
+    DetachedBackend::clear();
+}
+
+/// 窗口关闭/应用退出时调用：detached 模式下保留后端运行，否则正常清理
+fn release_python_process(app_handle: &tauri::AppHandle) {
+    let _turn = lifecycle_turn_blocking(app_handle);
+    if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+        {
+            let mut child = lock_process(&state);
+            if child.config().detached {
+                // 只丢弃句柄，不结束进程；状态文件保留供下次接管
+                if let Some(process) = child.take_process() {
+                    println!("[tauri] Leaving detached backend running (PID: {})", process.id());
+                }
+                return;
+            }
+        }
+    }
+    cleanup_python_process(app_handle);
+}
+
+/// 占用端口进程的归属
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum PortOwnerKind {
+    /// aestivus 自己启动的后端（残留实例），可以安全结束
+    Ours,
+    /// 与 aestivus 无关的程序，不能结束
+    Foreign,
+}
+
+/// 占用端口的进程信息
+#[derive(Debug, Clone, Serialize)]
+struct PortOwner {
+    port: u16,
+    pid: u32,
+    name: Option<String>,
+    cmdline: Option<String>,
+    kind: PortOwnerKind,
+}
+
+/// 列出监听指定端口的进程 PID
+fn listening_pids(port: u16) -> Vec<u32> {
+    let mut pids = Vec::new();
+    
+    #[cfg(target_os = "windows")]
+    if let Ok(output) = Command::new("netstat")
+        .args(["-aon", "-p", "TCP"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+    {
+        let suffix = format!(":{}", port);
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() >= 5 && fields[1].ends_with(&suffix) && fields[3] == "LISTENING" {
+                if let Ok(pid) = fields[4].parse() {
+                    pids.push(pid);
+                }
+            }
+        }
+    }
+    
+    #[cfg(not(target_os = "windows"))]
+    if let Ok(output) = Command::new("lsof")
+        .args(["-ti", &format!("tcp:{}", port), "-sTCP:LISTEN"])
+        .output()
+    {
+        pids.extend(
+            String::from_utf8_lossy(&output.stdout)
+                .split_whitespace()
+                .filter_map(|pid| pid.parse::<u32>().ok()),
+        );
+    }
+    
+    pids.sort_unstable();
+    pids.dedup();
+    pids
+}
+
+/// 命令行是否是 aestivus 的后端：第一个参数是 Python 解释器，且带有 `-m <module>`
+///
+/// 模块名按整个参数比较，只按子串匹配会把仓库路径下的其他程序（Tauri 应用本身、vite 开发服务器等）当成后端结束掉。
+fn is_backend_cmdline(cmd: &[String], module: &str) -> bool {
+    let Some((program, args)) = cmd.split_first() else {
+        return false;
     };
+    // 同时按 / 和 \ 取文件名，Windows 路径在其他平台上也能识别
+    let name = program.rsplit(['/', '\\']).next().unwrap_or(program);
+    let is_python = name.to_ascii_lowercase().starts_with("python");
+    is_python && args.windows(2).any(|pair| pair[0] == "-m" && pair[1] == module)
+}
+
+/// 查询占用端口的进程，并按命令行判断是否为 aestivus 的后端
+///
+/// 命令行包含后端模块名（或 "aestiv"）即视为自己的进程；`python_path` 为具体路径时，
+/// 可执行文件与之相同也视为自己的进程（"python" 这类裸命令名无法区分，不参与判断）。
+fn port_owners(port: u16, config: &PythonConfig) -> Vec<PortOwner> {
+    use sysinfo::{Pid, System};
+    
+    let mut system = System::new();
+    
+    listening_pids(port)
+        .into_iter()
+        .map(|pid| {
+            let sys_pid = Pid::from_u32(pid);
+            system.refresh_process(sys_pid);
+            let process = system.process(sys_pid);
+            let name = process.map(|p| p.name().to_string());
+            let cmdline = process.map(|p| p.cmd().join(" ")).filter(|c| !c.is_empty());
+            
+            let ours = process.is_some_and(|p| is_backend_cmdline(p.cmd(), &config.module));
+            
+            PortOwner {
+                port,
+                pid,
+                name,
+                cmdline,
+                kind: if ours { PortOwnerKind::Ours } else { PortOwnerKind::Foreign },
+            }
+        })
+        .collect()
+}
+
+/// 结束占用指定端口的残留后端进程，返回未处理的外部进程
+fn cleanup_python_ports(ports: &[u16], config: &PythonConfig) -> Vec<PortOwner> {
+    let mut foreign = Vec::new();
+    let mut killed = Vec::new();
+    
+    for port in ports {
+        for owner in port_owners(*port, config) {
+            if owner.kind == PortOwnerKind::Foreign {
+                println!(
+                    "[tauri] Port {} is held by foreign process {} (PID: {}), leaving it alone",
+                    owner.port,
+                    owner.name.as_deref().unwrap_or("unknown"),
+                    owner.pid
+                );
+                foreign.push(owner);
+                continue;
+            }
+            
+            println!("[tauri] Killing leftover backend on port {} (PID: {})", owner.port, owner.pid);
+            kill_pid(owner.pid);
+            killed.push(owner.pid);
+        }
+    }
+    
+    if !killed.is_empty() && !wait_for_pids_gone(&killed, config.post_kill_timeout_ms) {
+        println!("[tauri] Some killed processes are still alive after {}ms: {:?}", config.post_kill_timeout_ms, killed);
+    }
+    
+    foreign
+}
+
+/// 强制结束指定 PID 的进程（非子进程）
+fn kill_pid(pid: u32) {
+    let pid = pid.to_string();
+    
+    #[cfg(target_os = "windows")]
+    let _ = Command::new("taskkill")
+        .args(["/F", "/PID", &pid])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output();
     
     #[cfg(not(target_os = "windows"))]
-    let child = {
-        // macOS/Linux: 使用终端模拟器打开
-        let terminal_cmd = if cfg!(target_os = "macos") {
-            format!("osascript -e 'tell app \"Terminal\" to do script \"{} {}\"'", 
-                config.python_path, args.join(" "))
-        } else {
-            // Linux: 尝试常见的终端模拟器
-            format!("x-terminal-emulator -e {} {}", config.python_path, args.join(" "))
+    let _ = Command::new("kill").args(["-9", &pid]).output();
+}
+
+/// 轮询等待指定 PID 全部退出（非子进程无法 wait，只能检查是否仍存在）
+fn wait_for_pids_gone(pids: &[u32], timeout_ms: u64) -> bool {
+    use sysinfo::{Pid, System};
+    
+    let mut system = System::new();
+    let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+    loop {
+        if pids.iter().all(|pid| !system.refresh_process(Pid::from_u32(*pid))) {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+// ============== 资源监控 ==============
+
+/// 后端进程资源占用采样
+#[derive(Debug, Clone, Serialize)]
+struct ResourceSample {
+    cpu_percent: f32,
+    memory_bytes: u64,
+}
+
+/// 启动后台采样线程：按间隔读取当前后端 PID 的 CPU/内存并发送 `python-resources` 事件
+///
+/// 每轮都重新读取 PID，进程退出时停止上报，重启后自动跟随新 PID。
+fn start_resource_monitor(app_handle: tauri::AppHandle, interval_ms: u64) {
+    use sysinfo::{Pid, System};
+    
+    let interval = Duration::from_millis(interval_ms.max(100));
+    println!("[tauri] Resource monitor enabled (interval: {}ms)", interval.as_millis());
+    
+    std::thread::spawn(move || {
+        let mut system = System::new();
+        let mut last_pid: Option<u32> = None;
+        
+        loop {
+            std::thread::sleep(interval);
+            
+            let pid = match app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+                Some(state) => {
+                    let mut guard = lock_process(&state);
+                    guard.is_running().then(|| guard.pid()).flatten()
+                }
+                None => return,
+            };
+            
+            let Some(pid) = pid else {
+                last_pid = None;
+                continue;
+            };
+            
+            let sys_pid = Pid::from_u32(pid);
+            if last_pid != Some(pid) {
+                // 新进程：首次刷新只建立 CPU 基线，下一轮才有有效的占用率
+                println!("[tauri] Resource monitor tracking PID {}", pid);
+                system = System::new();
+                system.refresh_process(sys_pid);
+                last_pid = Some(pid);
+                continue;
+            }
+            
+            if !system.refresh_process(sys_pid) {
+                continue;
+            }
+            if let Some(process) = system.process(sys_pid) {
+                let _ = app_handle.emit(events::RESOURCES, ResourceSample {
+                    cpu_percent: process.cpu_usage(),
+                    memory_bytes: process.memory(),
+                });
+            }
+        }
+    });
+}
+
+/// 启动心跳线程：后端进程运行期间按间隔发送 `python-heartbeat` 事件
+///
+/// 与资源采样一样每轮重新读取进程状态，进程退出时停发，重启后带上新 PID。
+/// 只有状态为 running（已通过就绪检查）时 `healthy` 为 true，启动中或关闭中的进程发送不健康的心跳。
+fn start_heartbeat(app_handle: tauri::AppHandle, interval_ms: u64) {
+    let interval = Duration::from_millis(interval_ms);
+    println!("[tauri] Heartbeat enabled (interval: {}ms)", interval_ms);
+    
+    std::thread::spawn(move || loop {
+        std::thread::sleep(interval);
+        
+        let beat = match app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+            Some(state) => {
+                let mut guard = lock_process(&state);
+                let backend_state = guard.state();
+                guard
+                    .is_running()
+                    .then(|| guard.pid().zip(guard.uptime()))
+                    .flatten()
+                    .map(|(pid, uptime)| (pid, uptime, backend_state))
+            }
+            None => return,
         };
         
-        Command::new("sh")
-            .args(["-c", &terminal_cmd])
-            .current_dir("../src-python")
-            .spawn()
-            .or_else(|_| {
-                // 回退：直接启动（无可见终端）
-                Command::new(&config.python_path)
-                    .args(&args)
-                    .current_dir("../src-python")
-                    .stdout(Stdio::piped())
-                    .stderr(Stdio::piped())
-                    .spawn()
-            })
-            .map_err(|e| {
-                let msg = format!("Failed to spawn Python: {}", e);
-                println!("[tauri] {}", msg);
-                let _ = app_handle.emit("python-error", msg.clone());
-                msg
-            })?
+        if let Some((pid, uptime, backend_state)) = beat {
+            let _ = app_handle.emit(events::HEARTBEAT, serde_json::json!({
+                "pid": pid,
+                "uptime_ms": uptime.as_millis() as u64,
+                "healthy": backend_state == BackendState::Running,
+                "state": backend_state,
+            }));
+        }
+    });
+}
+
+/// 以 Prometheus 文本格式输出后端生命周期指标
+fn render_metrics(app_handle: &tauri::AppHandle) -> String {
+    let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() else {
+        return String::new();
+    };
+    let mut guard = lock_process(&state);
+    let up = guard.is_running();
+    let mut metrics = vec![
+        ("aestiv_backend_up", "gauge", "Whether the backend process is running.", if up { 1.0 } else { 0.0 }),
+        (
+            "aestiv_uptime_seconds",
+            "gauge",
+            "Seconds since the current backend process was started.",
+            guard.uptime().map(|d| d.as_secs_f64()).unwrap_or(0.0),
+        ),
+        ("aestiv_start_total", "counter", "Backend processes started by this app run.", guard.start_count() as f64),
+        (
+            "aestiv_restart_total",
+            "counter",
+            "Backend starts after the first one.",
+            guard.start_count().saturating_sub(1) as f64,
+        ),
+        ("aestiv_backend_port", "gauge", "Port the backend listens on.", guard.actual_port() as f64),
+    ];
+    if let Some(code) = guard.last_exit_code() {
+        metrics.push(("aestiv_last_exit_code", "gauge", "Exit code of the last backend process that exited.", code as f64));
+    }
+    
+    metrics
+        .into_iter()
+        .map(|(name, kind, help, value)| format!("# HELP {name} {help}\n# TYPE {name} {kind}\n{name} {value}\n"))
+        .collect()
+}
+
+/// 启动本地指标服务：GET /metrics 返回 Prometheus 文本格式的指标，其他路径返回 404
+/// 每个连接在单独的线程中处理，慢客户端不会挡住其他抓取
+fn start_metrics_server(app_handle: tauri::AppHandle, port: u16) {
+    use std::io::{Read, Write};
+    
+    let listener = match std::net::TcpListener::bind(("127.0.0.1", port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            println!("[tauri] Failed to start metrics server on port {}: {}", port, e);
+            return;
+        }
+    };
+    println!("[tauri] Metrics available at http://127.0.0.1:{}/metrics", port);
+    
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+            let app_handle = app_handle.clone();
+            std::thread::spawn(move || {
+                let _ = stream.set_read_timeout(Some(Duration::from_secs(2)));
+                let _ = stream.set_write_timeout(Some(Duration::from_secs(2)));
+                let mut buf = [0u8; 1024];
+                let len = stream.read(&mut buf).unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..len]);
+                let path = request.split_whitespace().nth(1).unwrap_or("");
+                
+                let response = if path == "/metrics" {
+                    let body = render_metrics(&app_handle);
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                } else {
+                    "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+                };
+                let _ = stream.write_all(response.as_bytes());
+            });
+        }
+    });
+}
+
+/// 启动空闲检查线程：超过 `timeout_ms` 没有活动时停止自己启动的后端
+fn start_idle_watcher(app_handle: tauri::AppHandle, timeout_ms: u64) {
+    let timeout = Duration::from_millis(timeout_ms);
+    let interval = Duration::from_millis((timeout_ms / 10).clamp(250, 5000));
+    println!("[tauri] Idle shutdown enabled (timeout: {}ms)", timeout_ms);
+    
+    std::thread::spawn(move || loop {
+        std::thread::sleep(interval);
+        
+        let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() else {
+            return;
+        };
+        let is_idle = |guard: &mut PythonProcess| {
+            guard.is_running() && !guard.idle_stopped() && guard.idle_for() >= timeout
+        };
+        let start_count = {
+            let mut guard = lock_process(&state);
+            if !is_idle(&mut *guard) {
+                continue;
+            }
+            guard.start_count()
+        };
+        
+        // 等待轮次期间可能有新的活动或重新启动，拿到轮次后重新判断
+        let _turn = lifecycle_turn_blocking(&app_handle);
+        let idle = {
+            let mut guard = lock_process(&state);
+            if guard.start_count() != start_count || !is_idle(&mut *guard) {
+                continue;
+            }
+            guard.set_idle_stopped(true);
+            guard.idle_for()
+        };
+        println!("[tauri] Backend idle for {}ms, stopping...", idle.as_millis());
+        cleanup_python_process(&app_handle);
+        let _ = app_handle.emit(events::IDLE_STOPPED, serde_json::json!({
+            "idle_ms": idle.as_millis() as u64,
+        }));
+    });
+}
+
+// ============== 配置文件监听 ==============
+
+/// 配置文件连续写入时的合并窗口
+const CONFIG_WATCH_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// 重新从磁盘加载配置（沿用文件中的 active profile），可选重启后端
+///
+/// 当前配置文件解析失败时（例如写到一半）保留现有配置。
+fn reload_config_from_disk(app_handle: &tauri::AppHandle, restart: bool) -> Result<PythonConfig, String> {
+    let state = app_handle
+        .try_state::<Arc<Mutex<PythonProcess>>>()
+        .ok_or("State not found")?;
+    let current_path = lock_process(&state).config_path().map(|p| p.to_string());
+    
+    let loaded = PythonConfig::load_detailed(None);
+    for warning in &loaded.warnings {
+        let _ = app_handle.emit(events::CONFIG_WARNING, warning);
+    }
+    if let Some(failed) = loaded
+        .warnings
+        .iter()
+        .find(|w| w.error.is_some() && Some(&w.path) == current_path.as_ref())
+    {
+        return Err(format!(
+            "Failed to reload {}: {}; keeping current config",
+            failed.path,
+            failed.error.as_deref().unwrap_or_default()
+        ));
+    }
+    
+    let (is_primary, has_process) = {
+        let mut guard = lock_process(&state);
+        guard.set_config(loaded.config.clone());
+        guard.set_config_warnings(loaded.warnings.clone());
+        guard.set_config_path(loaded.path.clone());
+        guard.set_configured_python_path(loaded.configured_python_path.clone());
+        (guard.is_primary(), guard.has_process())
+    };
+    println!("[tauri] Python config reloaded from {}", loaded.path.as_deref().unwrap_or("defaults"));
+    
+    if restart && has_process {
+        println!("[tauri] Restarting Python backend after config change...");
+        lock_process(&state).record_lifecycle("restart", "config file changed".to_string());
+        let _turn = lifecycle_turn_blocking(app_handle);
+        cleanup_python_process(app_handle);
+        spawn_python_backend(app_handle.clone(), is_primary, StartReason::ConfigReload).map_err(|e| e.message)?;
+    }
+    
+    Ok(loaded.config)
+}
+
+/// 监听配置文件变化：合并连续写入后重新加载配置
+///
+/// 监听所在目录而不是文件本身，编辑器"写临时文件再替换"的保存方式也能被捕获。
+fn start_config_watcher(app_handle: tauri::AppHandle, path: String) {
+    use notify::{RecursiveMode, Watcher};
+    
+    std::thread::spawn(move || {
+        let path = PathBuf::from(path);
+        let dir = path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or_else(|| std::path::Path::new("."))
+            .to_path_buf();
+        let file_name = path.file_name().map(|name| name.to_os_string());
+        
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut watcher = match notify::recommended_watcher(tx) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                println!("[tauri] Failed to create config watcher: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+            println!("[tauri] Failed to watch {:?}: {}", dir, e);
+            return;
+        }
+        println!("[tauri] Watching config file {:?} for changes", path);
+        
+        let touches_config = |event: &notify::Result<notify::Event>| match event {
+            Ok(event) => {
+                !event.kind.is_access()
+                    && event.paths.iter().any(|p| p.file_name() == file_name.as_deref())
+            }
+            Err(_) => false,
+        };
+        
+        while let Ok(event) = rx.recv() {
+            if !touches_config(&event) {
+                continue;
+            }
+            // 合并短时间内的连续写入
+            while rx.recv_timeout(CONFIG_WATCH_DEBOUNCE).is_ok() {}
+            
+            // 应用自己保存配置（persist_python_config）触发的事件不需要重新加载
+            let content = fs::read_to_string(&path).ok();
+            let restart = match app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+                Some(state) => {
+                    let guard = lock_process(&state);
+                    if content.as_deref().is_some_and(|c| guard.is_persisted_content(c)) {
+                        continue;
+                    }
+                    guard.config().restart_on_config_change
+                }
+                None => return,
+            };
+            println!("[tauri] Config file changed, reloading...");
+            if let Err(e) = reload_config_from_disk(&app_handle, restart) {
+                println!("[tauri] {}", e);
+            }
+        }
+    });
+}
+
+// ============== Tauri 命令 ==============
+
+#[tauri::command]
+fn greet(name: &str) -> String {
+    format!("Hello, {}! You've been greeted from Rust!", name)
+}
+
+/// 切换全屏，返回切换后的全屏状态
+#[tauri::command]
+fn toggle_fullscreen(window: tauri::Window) -> Result<bool, String> {
+    let is_fullscreen = window
+        .is_fullscreen()
+        .map_err(|e| format!("Failed to query fullscreen state: {}", e))?;
+    window
+        .set_fullscreen(!is_fullscreen)
+        .map_err(|e| format!("Failed to set fullscreen: {}", e))?;
+    Ok(!is_fullscreen)
+}
+
+/// 窗口状态
+#[derive(Debug, Clone, Serialize)]
+struct WindowInfo {
+    label: String,
+    fullscreen: bool,
+    maximized: bool,
+    visible: bool,
+    focused: bool,
+    width: u32,
+    height: u32,
+}
+
+/// 列出所有窗口及其状态（按 label 排序）
+#[tauri::command]
+fn list_windows(app_handle: tauri::AppHandle) -> Vec<WindowInfo> {
+    let mut windows: Vec<WindowInfo> = app_handle
+        .webview_windows()
+        .into_iter()
+        .map(|(label, window)| {
+            let size = window.inner_size().unwrap_or_default();
+            WindowInfo {
+                label,
+                fullscreen: window.is_fullscreen().unwrap_or(false),
+                maximized: window.is_maximized().unwrap_or(false),
+                visible: window.is_visible().unwrap_or(false),
+                focused: window.is_focused().unwrap_or(false),
+                width: size.width,
+                height: size.height,
+            }
+        })
+        .collect();
+    windows.sort_by(|a, b| a.label.cmp(&b.label));
+    windows
+}
+
+/// 设置指定 label 窗口的全屏状态
+#[tauri::command]
+fn set_window_fullscreen(app_handle: tauri::AppHandle, label: String, value: bool) -> Result<(), String> {
+    let window = app_handle
+        .get_webview_window(&label)
+        .ok_or_else(|| format!("Window '{}' not found", label))?;
+    window
+        .set_fullscreen(value)
+        .map_err(|e| format!("Failed to set fullscreen: {}", e))
+}
+
+/// python-error 事件负载
+#[derive(Debug, Clone, Serialize)]
+pub struct BackendError {
+    /// 错误码：PYTHON_NOT_FOUND / PYTHON_PATH_IS_DIRECTORY / PACKAGE_NOT_INSTALLED / PACKAGE_BROKEN / SPAWN_FAILED / PORT_CONFLICT / STARTUP_TIMEOUT
+    pub code: &'static str,
+    /// 可读的错误信息
+    pub message: String,
+}
+
+impl From<String> for BackendError {
+    fn from(message: String) -> Self {
+        Self { code: "INTERNAL_ERROR", message }
+    }
+}
+
+/// 进入新的启动阶段：记录到状态中并发送 python-startup-progress 事件
+fn set_startup_phase(app_handle: &tauri::AppHandle, phase: StartupPhase, detail: Option<String>) {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let progress = StartupProgress { phase, detail, timestamp };
+    if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+        lock_process(&state).set_startup_progress(progress.clone());
+    }
+    let _ = app_handle.emit(events::STARTUP_PROGRESS, progress);
+}
+
+/// 记录并向前端发送 python-error 事件，返回错误以便直接作为 Err 使用
+fn emit_backend_error(app_handle: &tauri::AppHandle, code: &'static str, message: String) -> BackendError {
+    println!("[tauri] Error [{}]: {}", code, message);
+    // 所有启动错误都经过这里，同时结束启动进度
+    set_startup_phase(app_handle, StartupPhase::Failed, Some(format!("{}: {}", code, message)));
+    if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+        lock_process(&state).record_lifecycle("error", format!("{}: {}", code, message));
+    }
+    let error = BackendError { code, message };
+    let _ = app_handle.emit(events::ERROR, error.clone());
+    error
+}
+
+/// 轮询就绪检查路径，直到后端返回成功或超时
+async fn wait_for_backend_ready(app_handle: &tauri::AppHandle, readiness_path: &str, timeout_ms: u64) -> bool {
+    let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+    
+    loop {
+        if let Ok(resp) = backend_request(app_handle, "GET", readiness_path, None).await {
+            if resp.is_success() {
+                return true;
+            }
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(Duration::from_millis(250)).await;
+    }
+}
+
+/// 后端进程是否仍在运行（复用现有服务时没有进程，视为运行中）
+fn backend_alive(app_handle: &tauri::AppHandle) -> bool {
+    match app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+        Some(state) => {
+            let mut guard = lock_process(&state);
+            !guard.has_process() || guard.is_running()
+        }
+        None => false,
+    }
+}
+
+/// 单次 TCP 连接探测的超时时间
+const TCP_PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// TCP 连接探测结果
+#[derive(Debug, Clone, Serialize)]
+struct ConnectResult {
+    connected: bool,
+    /// 建立连接耗时（未连接时为 None）
+    latency_ms: Option<u64>,
+}
+
+/// 尝试建立 TCP 连接（不发送任何数据），用于比健康检查更早的存活判断
+async fn probe_tcp(host: &str, port: u16, timeout: Duration) -> ConnectResult {
+    let started = Instant::now();
+    match tokio::time::timeout(timeout, tokio::net::TcpStream::connect((host, port))).await {
+        Ok(Ok(_)) => ConnectResult { connected: true, latency_ms: Some(started.elapsed().as_millis() as u64) },
+        _ => ConnectResult { connected: false, latency_ms: None },
+    }
+}
+
+/// 按配置的策略等待后端启动完成；进程提前退出或超时返回 false
+///
+/// `log_offset` 为启动前日志文件的长度，stdout_marker 只在之后写入的内容中查找。
+async fn wait_for_readiness(
+    app_handle: &tauri::AppHandle,
+    strategy: &ReadinessStrategy,
+    readiness_path: &str,
+    host: &str,
+    port: u16,
+    #[cfg_attr(not(target_os = "windows"), allow(unused_variables))] log_offset: u64,
+    timeout_ms: u64,
+) -> bool {
+    let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+    
+    match strategy {
+        ReadinessStrategy::None => true,
+        ReadinessStrategy::HttpPoll { path } => {
+            let path = path.as_deref().unwrap_or(readiness_path);
+            wait_for_backend_ready(app_handle, path, timeout_ms).await
+        }
+        #[cfg(target_os = "windows")]
+        ReadinessStrategy::StdoutMarker(marker) => {
+            use std::io::{Read, Seek, SeekFrom};
+            
+            let log_path = get_python_log_path();
+            loop {
+                let mut output = String::new();
+                if let Ok(mut file) = fs::File::open(&log_path) {
+                    if file.seek(SeekFrom::Start(log_offset)).is_ok() {
+                        let _ = file.read_to_string(&mut output);
+                    }
+                }
+                if output.contains(marker.as_str()) {
+                    return true;
+                }
+                if Instant::now() >= deadline || !backend_alive(app_handle) {
+                    return false;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }
+        // 非 Windows 上 stdout_marker 已被 validate 拒绝
+        _ => {
+            loop {
+                if probe_tcp(host, port, TCP_PROBE_TIMEOUT).await.connected {
+                    return true;
+                }
+                if Instant::now() >= deadline || !backend_alive(app_handle) {
+                    return false;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }
+    }
+}
+
+/// 等待后端就绪检查路径可访问后发送预热请求，失败只记录日志
+async fn warmup_backend(
+    app_handle: tauri::AppHandle,
+    readiness_path: String,
+    warmup: WarmupConfig,
+    timeout_ms: u64,
+) {
+    if !wait_for_backend_ready(&app_handle, &readiness_path, timeout_ms).await {
+        println!("[tauri] Warmup skipped: backend not healthy within {}ms", timeout_ms);
+        return;
+    }
+    
+    println!("[tauri] Sending warmup request: {} {}", warmup.method, warmup.path);
+    let started = Instant::now();
+    match backend_request(&app_handle, &warmup.method, &warmup.path, warmup.body.as_ref()).await {
+        Ok(resp) => {
+            let elapsed_ms = started.elapsed().as_millis() as u64;
+            println!("[tauri] Warmup finished with status {} in {}ms", resp.status, elapsed_ms);
+            let _ = app_handle.emit(events::WARMED, serde_json::json!({
+                "status": resp.status,
+                "elapsed_ms": elapsed_ms,
+            }));
+        }
+        Err(e) => println!("[tauri] Warmup request failed: {}", e),
+    }
+}
+
+/// 向后端传递鉴权令牌的环境变量名
+const AUTH_TOKEN_ENV: &str = "AESTIV_AUTH_TOKEN";
+
+/// 启动后端进程（不做任何检查）
+///
+/// 返回子进程以及它是否就是 Python 本身：经终端或 launcher 启动时子进程只是启动命令。
+/// detached 为 true 时后端会比应用活得更久，输出不能接到没人读取的管道上。
+fn launch_backend_process(
+    launcher: &[String],
+    python_path: &str,
+    args: &[&str],
+    envs: &[(&str, &str)],
+    env_mode: EnvMode,
+    confirm_ms: u64,
+    #[cfg_attr(target_os = "windows", allow(unused_variables))] detached: bool,
+) -> Result<(Child, bool), String> {
+    // 配置了 launcher（如 ["uv", "run"]）时实际执行 `uv run python -m ...`
+    let (program, args) = match launcher.split_first() {
+        Some((program, rest)) => {
+            let mut full: Vec<&str> = rest.iter().map(|s| s.as_str()).collect();
+            full.push(python_path);
+            full.extend_from_slice(args);
+            (program.as_str(), full)
+        }
+        None => (python_path, args.to_vec()),
+    };
+    println!("[tauri] Spawning: {} {:?}", program, args);
+    let venv_envs = venv_env(python_path);
+    
+    // Windows: 静默后台启动，日志写入文件
+    #[cfg(target_os = "windows")]
+    let child = {
+        use std::process::Stdio;
+        
+        let log_path = get_python_log_path();
+        println!("[tauri] Python backend log: {:?}", log_path);
+        
+        // 打开日志文件（追加模式）
+        let log_file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&log_path)
+            .map_err(|e| {
+                let msg = format!("Failed to open log file: {}", e);
+                println!("[tauri] {}", msg);
+                msg
+            })?;
+        
+        let log_file_err = log_file.try_clone().map_err(|e| {
+            let msg = format!("Failed to clone log file handle: {}", e);
+            println!("[tauri] {}", msg);
+            msg
+        })?;
+        
+        // 静默启动 Python 进程，无控制台窗口
+        // 设置 PYTHONIOENCODING=utf-8 避免 Windows GBK 编码问题
+        // stdin 保持为管道，用于发送 "sidecar shutdown" 等控制命令
+        backend_command(program, env_mode)
+            .args(&args)
+            .envs(envs.iter().copied())
+            .envs(venv_envs.iter().cloned())
+            .env("PYTHONIOENCODING", "utf-8")
+            .env("PYTHONUTF8", "1")
+            .current_dir("../src-python")
+            .creation_flags(CREATE_NO_WINDOW)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::from(log_file))
+            .stderr(Stdio::from(log_file_err))
+            .spawn()
+            .map_err(|e| format!("Failed to spawn Python '{}': {}", program, e))
+            .and_then(|child| confirm_spawn(child, confirm_ms))
+            .map(|child| (child, launcher.is_empty()))
+    };
+    
+    #[cfg(not(target_os = "windows"))]
+    let child = {
+        // macOS/Linux: 使用终端模拟器打开
+        let terminal_cmd = if cfg!(target_os = "macos") {
+            format!("osascript -e 'tell app \"Terminal\" to do script \"{} {}\"'", 
+                program, args.join(" "))
+        } else {
+            // Linux: 尝试常见的终端模拟器
+            format!("x-terminal-emulator -e {} {}", program, args.join(" "))
+        };
+        
+        // 终端模式下 Python 的 stdin 属于终端，无法发送控制命令，关闭时走信号
+        // 此时子进程是终端启动命令而不是 Python 本身，无法确认存活
+        match backend_command("sh", env_mode)
+            .args(["-c", &terminal_cmd])
+            .envs(envs.iter().copied())
+            .envs(venv_envs.iter().cloned())
+            .current_dir("../src-python")
+            .spawn()
+        {
+            Ok(child) => Ok((child, false)),
+            // 回退：直接启动（无可见终端），stdin 保持为管道用于控制命令
+            Err(_) => {
+                let (stdout, stderr) = if detached {
+                    detached_log_stdio()
+                } else {
+                    (Stdio::piped(), Stdio::piped())
+                };
+                backend_command(program, env_mode)
+                    .args(&args)
+                    .envs(envs.iter().copied())
+                    .envs(venv_envs.iter().cloned())
+                    .current_dir("../src-python")
+                    .stdin(Stdio::piped())
+                    .stdout(stdout)
+                    .stderr(stderr)
+                    .spawn()
+                    .map_err(|e| format!("Failed to spawn Python '{}': {}", program, e))
+                    .and_then(|child| confirm_spawn(child, confirm_ms))
+                    .map(|child| (child, launcher.is_empty()))
+            }
+        }
+    };
+    
+    child
+}
+
+/// detached 后端的输出：应用退出后管道无人读取，后端写输出时会因 EPIPE 退出，
+/// 因此改为写入日志文件，日志文件打不开时丢弃
+#[cfg(not(target_os = "windows"))]
+fn detached_log_stdio() -> (Stdio, Stdio) {
+    let log_file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(get_python_log_path());
+    match log_file.and_then(|file| Ok((file.try_clone()?, file))) {
+        Ok((stdout, stderr)) => (Stdio::from(stdout), Stdio::from(stderr)),
+        Err(e) => {
+            println!("[tauri] Failed to open log file for detached backend: {}", e);
+            (Stdio::null(), Stdio::null())
+        }
+    }
+}
+
+/// clean 模式下保留的环境变量：进程运行、临时目录、区域设置以及终端/图形会话所需
+const ESSENTIAL_ENV_VARS: [&str; 18] = [
+    "PATH", "HOME", "USER", "USERPROFILE", "SYSTEMROOT", "SYSTEMDRIVE", "WINDIR", "COMSPEC",
+    "APPDATA", "LOCALAPPDATA", "TEMP", "TMP", "TMPDIR", "LANG", "LC_ALL",
+    "DISPLAY", "WAYLAND_DISPLAY", "XDG_RUNTIME_DIR",
+];
+
+/// 创建启动后端用的命令，按 env_mode 决定是否清空继承的环境变量
+fn backend_command(program: &str, env_mode: EnvMode) -> Command {
+    let mut cmd = Command::new(program);
+    if env_mode == EnvMode::Clean {
+        cmd.env_clear();
+        for key in ESSENTIAL_ENV_VARS {
+            if let Some(value) = std::env::var_os(key) {
+                cmd.env(key, value);
+            }
+        }
+    }
+    cmd
+}
+
+/// 启动后短暂确认进程仍在运行，把"启动即退出"（参数错误、缺少模块等）当作启动失败而不是崩溃
+fn confirm_spawn(mut child: Child, confirm_ms: u64) -> Result<Child, String> {
+    use std::io::Read;
+    
+    if confirm_ms == 0 || !wait_for_exit(&mut child, confirm_ms) {
+        return Ok(child);
+    }
+    let status = child
+        .try_wait()
+        .ok()
+        .flatten()
+        .map(|status| status.to_string())
+        .unwrap_or_else(|| "unknown status".to_string());
+    
+    // stderr 为管道时附带最后一行（通常是异常信息）；写入日志文件时提示查看日志
+    let mut stderr = String::new();
+    if let Some(mut pipe) = child.stderr.take() {
+        let _ = pipe.read_to_string(&mut stderr);
+    }
+    let reason = match stderr.lines().rev().find(|line| !line.trim().is_empty()) {
+        Some(line) => format!(": {}", line.trim()),
+        None if cfg!(target_os = "windows") => format!(", see {}", get_python_log_path().display()),
+        None => String::new(),
+    };
+    Err(format!("Python exited within {}ms of starting ({}){}", confirm_ms, status, reason))
+}
+
+/// 本次运行是否已经提示过非回环地址监听
+static EXTERNAL_BIND_WARNED: AtomicBool = AtomicBool::new(false);
+
+/// 后端启动结果
+#[derive(Debug, Clone, Serialize)]
+pub struct SpawnInfo {
+    /// 后端进程 PID（复用已有服务、或经终端/launcher 启动尚未确定时为 None）
+    pub pid: Option<u32>,
+    /// 实际使用的端口
+    pub port: u16,
+    /// 监听地址
+    pub host: String,
+}
+
+/// 首选端口不可用时的备选端口：配置了端口池时取池中第一个空闲端口
+///
+/// 端口池全被占用时，auto_port 开启则退回到顺序查找，否则报告 PORT_CONFLICT。
+fn pick_pool_port(app_handle: &tauri::AppHandle, config: &PythonConfig, preferred: u16) -> Result<u16, BackendError> {
+    if config.port_pool.is_empty() {
+        return Ok(find_available_port(config.connect_host(), preferred + 1));
+    }
+    if let Some(port) = config.port_pool.iter().copied().find(|port| !is_port_in_use(config.connect_host(), *port)) {
+        return Ok(port);
+    }
+    if config.auto_port {
+        println!("[tauri] All ports in port_pool are in use, searching outside the pool...");
+        return Ok(find_available_port(config.connect_host(), preferred + 1));
+    }
+    let msg = format!("All ports in port_pool {:?} are in use.", config.port_pool);
+    Err(emit_backend_error(app_handle, "PORT_CONFLICT", msg))
+}
+
+/// 启动 Python 后端进程（支持多实例）
+fn spawn_python_backend(app_handle: tauri::AppHandle, is_primary: bool, reason: StartReason) -> Result<SpawnInfo, BackendError> {
+    let config = if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+        let mut process_state = lock_process(&state);
+        if process_state.has_process() || process_state.is_attached() {
+            println!("[tauri] Python backend is already running.");
+            return Ok(SpawnInfo {
+                pid: process_state.pid(),
+                port: process_state.actual_port(),
+                host: process_state.config().host.clone(),
+            });
+        }
+        process_state.set_primary(is_primary);
+        process_state.config().clone()
+    } else {
+        return Err("Failed to access app state".to_string().into());
+    };
+
+    // detached 模式：上次留在后台的后端仍然健康时直接接管
+    if config.detached {
+        if let Some(saved) = DetachedBackend::load() {
+            if pid_alive(saved.pid) && check_aestivus_service(&config, saved.port) {
+                println!("[tauri] Reattaching to detached backend (PID: {}) on port {}", saved.pid, saved.port);
+                if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+                    let mut process_state = lock_process(&state);
+                    process_state.set_attached(saved.pid, saved.port);
+                    process_state.record_lifecycle("reattach", format!("pid {} on port {}", saved.pid, saved.port));
+                }
+                set_startup_phase(&app_handle, StartupPhase::Ready, Some(format!("reattached on port {}", saved.port)));
+                let _ = app_handle.emit(events::READY, saved.port);
+                return Ok(SpawnInfo { pid: Some(saved.pid), port: saved.port, host: config.host.clone() });
+            }
+            println!("[tauri] Detached backend (PID: {}) is gone, starting a new one", saved.pid);
+            DetachedBackend::clear();
+        }
+    }
+    
+    let default_port = config.preferred_port();
+    set_startup_phase(&app_handle, StartupPhase::CheckingPort, Some(default_port.to_string()));
+    
+    // 主实例逻辑：检查 8009 是否已有服务
+    if is_primary {
+        if is_port_in_use(config.connect_host(), default_port) {
+            // 端口被占用，检查是否是 aestivus 服务
+            if check_aestivus_service(&config, default_port) {
+                println!("[tauri] Found existing aestivus service on port {}, reusing...", default_port);
+                if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+                    let mut process_state = lock_process(&state);
+                    process_state.set_actual_port(default_port);
+                    process_state.set_reusing_backend();
+                    process_state.record_lifecycle("reuse", format!("existing service on port {}", default_port));
+                }
+                set_startup_phase(&app_handle, StartupPhase::Ready, Some(format!("reusing service on port {}", default_port)));
+                let _ = app_handle.emit(events::READY, default_port);
+                return Ok(SpawnInfo { pid: None, port: default_port, host: config.host.clone() });
+            } else {
+                // 端口被其他程序占用，找新端口
+                println!("[tauri] Port {} occupied by other service, finding new port...", default_port);
+            }
+        }
+    }
+    
+    // 确定要使用的端口
+    let actual_port = if is_primary && !is_port_in_use(config.connect_host(), default_port) {
+        default_port
+    } else {
+        // 如果是主实例但端口被占用，尝试清理后再检查一次
+        if is_primary {
+            println!("[tauri] Port {} occupied, attempting cleanup...", default_port);
+            let foreign = cleanup_python_ports(&config.cleanup_port_set(default_port), &config);
+            if let Some(owner) = foreign.iter().find(|owner| owner.port == default_port) {
+                // 端口池中还有其他端口可选时同样视为可以改用其他端口
+                let can_move = config.auto_port || config.port_pool.len() > 1;
+                let action = if can_move {
+                    "aestivus will not stop it and is starting on a different port."
+                } else {
+                    "aestivus will not stop it; free the port or enable auto_port."
+                };
+                let message = format!(
+                    "Port {} is used by another program ({}, PID {}). {}",
+                    owner.port,
+                    owner.name.as_deref().unwrap_or("unknown"),
+                    owner.pid,
+                    action
+                );
+                let _ = app_handle.emit(events::PORT_CONFLICT, serde_json::json!({
+                    "owner": owner,
+                    "message": message,
+                }));
+                // 外部程序占用端口时直接启动只会绑定失败，不启动
+                if !can_move {
+                    return Err(emit_backend_error(&app_handle, "PORT_CONFLICT", message));
+                }
+            }
+            if !is_port_in_use(config.connect_host(), default_port) {
+                default_port
+            } else {
+                pick_pool_port(&app_handle, &config, default_port)?
+            }
+        } else {
+            // 多开实例，找可用端口
+            pick_pool_port(&app_handle, &config, default_port)?
+        }
+    };
+    
+    println!("[tauri] Starting Python backend on port {} (primary: {})", actual_port, is_primary);
+    
+    // 解析可用的解释器：配置的路径不可用时回退到其他候选
+    // 使用 launcher 或 venv_path 时运行环境已确定，不回退到其他解释器
+    let launcher = config.launcher.clone().unwrap_or_default();
+    let allow_fallback = launcher.is_empty() && config.venv_path.is_none();
+    let configured_python = interpreter_for_path(&config.python_path)
+        .map_err(|msg| emit_backend_error(&app_handle, "PYTHON_PATH_IS_DIRECTORY", msg))?;
+    let mut python_path = if launcher.is_empty() {
+        resolve_python_executable(&app_handle, &configured_python)
+    } else {
+        configured_python
+    };
+    // 解析后的配置路径，之后换用其他候选才算回退
+    let resolved_python = python_path.clone();
+    let mut failed_paths = Vec::new();
+    set_startup_phase(&app_handle, StartupPhase::DetectingPython, Some(python_path.clone()));
+    if !is_python_available(&launcher, &python_path) {
+        failed_paths.push(python_path.clone());
+        let fallback = if allow_fallback { next_python_candidate(&failed_paths, &config.module) } else { None };
+        match fallback {
+            Some(candidate) => {
+                println!("[tauri] Python not found at '{}', falling back to '{}'", python_path, candidate);
+                python_path = candidate;
+            }
+            None => {
+                let msg = format!("Python not found at '{}'.", config.python_path);
+                return Err(emit_backend_error(&app_handle, "PYTHON_NOT_FOUND", msg));
+            }
+        }
+    }
+    
+    set_startup_phase(&app_handle, StartupPhase::CheckingPackage, Some(config.module.clone()));
+    match check_aestiv_package(&launcher, &python_path, &config.module) {
+        PackageStatus::Installed => {}
+        PackageStatus::NotInstalled => {
+            let msg = format!("{} package not found. Run: pip install -e ./src-python", config.module);
+            return Err(emit_backend_error(&app_handle, "PACKAGE_NOT_INSTALLED", msg));
+        }
+        PackageStatus::Broken(import_error) => {
+            let msg = format!(
+                "{} is installed but failed to import: {}. Fix the missing dependency instead of reinstalling {}.",
+                config.module, import_error, config.module
+            );
+            return Err(emit_backend_error(&app_handle, "PACKAGE_BROKEN", msg));
+        }
+    }
+    
+    // 构建启动参数（带端口）
+    let port_str = actual_port.to_string();
+    let mut args = vec!["-m", config.module.as_str(), "--port", &port_str, "--host", config.host.as_str()];
+    if config.dev_mode {
+        args.push("--standalone");
+    }
+    
+    let mut envs: Vec<(&str, &str)> = config.env.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+    if let Some(token) = config.auth_token.as_deref() {
+        envs.push((AUTH_TOKEN_ENV, token));
+    }
+    
+    let log_offset = fs::metadata(get_python_log_path()).map(|m| m.len()).unwrap_or(0);
+    
+    set_startup_phase(&app_handle, StartupPhase::Spawning, Some(format!("port {}", actual_port)));
+    // 启动失败时排除该解释器，依次尝试其他可用候选
+    let (child, child_is_backend) = loop {
+        match launch_backend_process(
+            &launcher,
+            &python_path,
+            &args,
+            &envs,
+            config.env_mode,
+            config.spawn_confirm_ms,
+            config.detached,
+        ) {
+            Ok(launched) => break launched,
+            Err(e) => {
+                failed_paths.push(python_path.clone());
+                let fallback = if allow_fallback { next_python_candidate(&failed_paths, &config.module) } else { None };
+                match fallback {
+                    Some(candidate) => {
+                        println!("[tauri] {} Retrying with '{}'...", e, candidate);
+                        if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+                            lock_process(&state).record_lifecycle("retry", format!("{} Retrying with '{}'", e, candidate));
+                        }
+                        python_path = candidate;
+                    }
+                    None => return Err(emit_backend_error(&app_handle, "SPAWN_FAILED", e)),
+                }
+            }
+        }
+    };
+    
+    if python_path != resolved_python {
+        println!("[tauri] Python backend started with fallback interpreter '{}'", python_path);
+        if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+            let mut process_state = lock_process(&state);
+            let updated = PythonConfig {
+                python_path: python_path.clone(),
+                ..process_state.config().clone()
+            };
+            process_state.set_config(updated);
+        }
+        let _ = app_handle.emit(events::FALLBACK, serde_json::json!({
+            "from": config.python_path,
+            "to": python_path,
+        }));
+    }
+    
+    if !config.binds_loopback_only() && !EXTERNAL_BIND_WARNED.swap(true, Ordering::SeqCst) {
+        println!("[tauri] WARNING: backend is bound to {} and reachable from other machines", config.host);
+        let _ = app_handle.emit(events::EXTERNAL_BIND, serde_json::json!({
+            "host": config.host,
+            "port": actual_port,
+        }));
+    }
+    
+    let pid = child.id();
+    println!("[tauri] Python process spawned with PID: {} on port {} (in new console window)", pid, actual_port);
+    
+    // 存储进程和端口
+    if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+        let mut process_state = lock_process(&state);
+        process_state.set_process(child, reason, child_is_backend);
+        process_state.set_actual_port(actual_port);
+        process_state.record_lifecycle("spawn", format!("pid {} on port {} via {} ({:?})", pid, actual_port, python_path, reason));
+    }
+    // 只有 PID 就是后端本身时才能保存；经终端或 launcher 启动的在就绪后按端口查到 PID 再保存
+    if config.detached && child_is_backend {
+        DetachedBackend { pid, port: actual_port }.save();
+    }
+    if config.port_pool.contains(&actual_port) {
+        PinnedPort { port: actual_port }.save();
+    }
+    set_startup_phase(&app_handle, StartupPhase::WaitingForReady, Some(format!("{:?}", config.readiness)));
+    
+    // 按 readiness 策略确认启动完成后再通知前端并预热
+    let ready_handle = app_handle.clone();
+    let ready_config = config.clone();
+    tauri::async_runtime::spawn(async move {
+        let readiness_path = ready_config.effective_readiness_path().to_string();
+        let timeout_ms = ready_config.startup_timeout_ms;
+        if !wait_for_readiness(
+            &ready_handle,
+            &ready_config.readiness,
+            &readiness_path,
+            ready_config.connect_host(),
+            actual_port,
+            log_offset,
+            timeout_ms,
+        ).await {
+            let msg = format!("Backend did not become ready within {}ms ({:?})", timeout_ms, ready_config.readiness);
+            emit_backend_error(&ready_handle, "STARTUP_TIMEOUT", msg);
+            return;
+        }
+        println!("[tauri] Python backend ready on port {}", actual_port);
+        if !child_is_backend {
+            // 经终端或 launcher 启动：按监听端口的进程确定真正的后端 PID
+            let lookup_config = ready_config.clone();
+            let backend_pid = tauri::async_runtime::spawn_blocking(move || find_backend_pid(actual_port, &lookup_config))
+                .await
+                .ok()
+                .flatten();
+            match backend_pid {
+                Some(backend_pid) => {
+                    println!("[tauri] Python backend PID resolved to {}", backend_pid);
+                    if let Some(state) = ready_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+                        lock_process(&state).set_backend_pid(backend_pid);
+                    }
+                    if ready_config.detached {
+                        DetachedBackend { pid: backend_pid, port: actual_port }.save();
+                    }
+                }
+                None => println!("[tauri] Could not resolve the backend PID on port {}", actual_port),
+            }
+        }
+        set_startup_phase(&ready_handle, StartupPhase::Ready, Some(format!("port {}", actual_port)));
+        if let Some(state) = ready_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+            lock_process(&state).set_state(BackendState::Running);
+        }
+        let _ = ready_handle.emit(events::READY, actual_port);
+        
+        if let Some(warmup) = ready_config.warmup.clone() {
+            warmup_backend(ready_handle, readiness_path, warmup, timeout_ms).await;
+        }
+    });
+
+    Ok(SpawnInfo { pid: child_is_backend.then_some(pid), port: actual_port, host: config.host.clone() })
+}
+
+
+
+#[tauri::command]
+fn shutdown_python(app_handle: tauri::AppHandle) -> Result<String, String> {
+    println!("[tauri] Shutting down Python backend...");
+    let _turn = lifecycle_turn_blocking(&app_handle);
+    cleanup_python_process(&app_handle);
+    Ok("Python backend shutdown.".to_string())
+}
+
+/// 重启整个应用，用于无法热更新的设置（插件初始化、窗口配置等）
+///
+/// 先按退出流程处理后端（detached 模式保留给重启后的实例接管，否则结束进程），避免留下孤儿进程。
+#[tauri::command]
+fn relaunch_app(app_handle: tauri::AppHandle) {
+    println!("[tauri] Relaunching app...");
+    release_python_process(&app_handle);
+    app_handle.restart();
+}
+
+/// 查询占用端口的进程及其归属（默认查询配置的端口）
+#[tauri::command]
+fn get_port_owners(app_handle: tauri::AppHandle, port: Option<u16>) -> Result<Vec<PortOwner>, String> {
+    let config = match app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+        Some(state) => lock_process(&state).config().clone(),
+        None => return Err("State not found".to_string()),
+    };
+    Ok(port_owners(port.unwrap_or(config.port), &config))
+}
+
+/// 向后端发送中断信号（相当于 Ctrl-C），由后端自行决定如何收尾退出
+///
+/// Unix 向后端 Python 进程（不是终端/launcher 启动命令）发送 SIGINT。
+/// Windows 上后端没有控制台，CTRL_C/CTRL_BREAK 送不到，改为通过 stdin 发送
+/// "sidecar shutdown"。后端收到后调用 `os.kill(os.getpid(), SIGINT)`，而在 Windows 上
+/// 这等同于 TerminateProcess：进程被立即结束，不会触发 KeyboardInterrupt 或清理逻辑。
+#[tauri::command]
+fn interrupt_python(app_handle: tauri::AppHandle) -> Result<(), String> {
+    let state = app_handle
+        .try_state::<Arc<Mutex<PythonProcess>>>()
+        .ok_or("State not found")?;
+    let mut guard = lock_process(&state);
+    if !guard.is_running() {
+        return Err("No Python process is running".to_string());
+    }
+    
+    #[cfg(unix)]
+    {
+        let pid = guard
+            .pid()
+            .ok_or("Backend PID is not known yet (started via a terminal or launcher)")?;
+        // SAFETY: kill(2) 只向指定 PID 发送信号，不涉及内存操作
+        if unsafe { libc::kill(pid as libc::pid_t, libc::SIGINT) } != 0 {
+            return Err(format!("Failed to send SIGINT: {}", std::io::Error::last_os_error()));
+        }
+        println!("[tauri] Sent SIGINT to Python process (PID: {})", pid);
+        Ok(())
+    }
+    
+    #[cfg(not(unix))]
+    {
+        let process = guard
+            .child_mut()
+            .ok_or("The backend was not started by this app, its stdin is unavailable")?;
+        if !request_stdin_shutdown(process, "sidecar shutdown") {
+            return Err("Failed to send the shutdown command to the backend's stdin".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// 统计进程打开的文件描述符（Windows 上为句柄）数量
+fn process_fd_count(pid: u32) -> Result<u64, String> {
+    #[cfg(target_os = "linux")]
+    {
+        let entries = fs::read_dir(format!("/proc/{}/fd", pid))
+            .map_err(|e| format!("Failed to read /proc/{}/fd: {}", pid, e))?;
+        Ok(entries.filter(|entry| entry.is_ok()).count() as u64)
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        // -F f 每个描述符输出一行 "f<fd>"，不受列宽和表头影响
+        let output = Command::new("lsof")
+            .args(["-n", "-P", "-F", "f", "-p", &pid.to_string()])
+            .output()
+            .map_err(|e| format!("Failed to run lsof: {}", e))?;
+        if !output.status.success() {
+            return Err(format!("lsof exited with {}", output.status));
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(stdout
+            .lines()
+            .filter(|line| line.strip_prefix('f').is_some_and(|fd| fd.parse::<u64>().is_ok()))
+            .count() as u64)
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        // SAFETY: 句柄仅在本块内使用，并在返回前关闭
+        unsafe {
+            let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+            if handle.is_null() {
+                return Err(format!("Failed to open process {}: {}", pid, std::io::Error::last_os_error()));
+            }
+            let mut count: u32 = 0;
+            let ok = GetProcessHandleCount(handle, &mut count);
+            let error = std::io::Error::last_os_error();
+            CloseHandle(handle);
+            if ok == 0 {
+                return Err(format!("Failed to query handle count: {}", error));
+            }
+            Ok(count as u64)
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    {
+        let _ = pid;
+        Err("Counting file descriptors is not supported on this platform".to_string())
+    }
+}
+
+/// 获取后端进程打开的文件描述符/句柄数量，用于排查句柄泄漏
+///
+/// Linux 读取 /proc/{pid}/fd；macOS 调用 lsof -p；Windows 查询进程句柄数。
+#[tauri::command]
+fn get_python_fd_count(app_handle: tauri::AppHandle) -> Result<u64, String> {
+    let state = app_handle
+        .try_state::<Arc<Mutex<PythonProcess>>>()
+        .ok_or("State not found")?;
+    let pid = {
+        let mut guard = lock_process(&state);
+        if !guard.is_running() {
+            return Err("No Python process is running".to_string());
+        }
+        guard.pid().ok_or("No Python process is running")?
+    };
+    process_fd_count(pid)
+}
+
+/// 停止后端并禁止自动拉起，直到调用 enable_python
+#[tauri::command]
+fn disable_and_stop_python(app_handle: tauri::AppHandle) -> Result<String, String> {
+    println!("[tauri] Disabling auto-restart and shutting down Python backend...");
+    if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+        let mut guard = lock_process(&state);
+        guard.set_auto_restart_override(Some(false));
+    } else {
+        return Err("State not found".to_string());
+    }
+    let _turn = lifecycle_turn_blocking(&app_handle);
+    cleanup_python_process(&app_handle);
+    Ok("Python backend stopped, auto-restart disabled.".to_string())
+}
+
+/// 清除 disable_and_stop_python 设置的覆盖，恢复配置中的 auto_restart
+#[tauri::command]
+fn enable_python(app_handle: tauri::AppHandle) -> Result<String, String> {
+    if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+        let mut guard = lock_process(&state);
+        guard.set_auto_restart_override(None);
+        println!("[tauri] Auto-restart override cleared (auto_restart: {})", guard.auto_restart());
+        Ok("Python backend enabled.".to_string())
+    } else {
+        Err("State not found".to_string())
+    }
+}
+
+/// 前端请求后端前调用：刷新活动时间；后端因空闲被停止时重新启动并等待就绪
+///
+/// 返回后端端口。
+#[tauri::command]
+async fn note_backend_activity(app_handle: tauri::AppHandle) -> Result<u16, String> {
+    let state = app_handle
+        .try_state::<Arc<Mutex<PythonProcess>>>()
+        .ok_or("State not found")?;
+    {
+        let mut guard = lock_process(&state);
+        guard.note_activity();
+        if !guard.idle_stopped() {
+            return Ok(guard.actual_port());
+        }
+    }
+    
+    // 等待轮次期间可能已被并发的调用或其他命令重新启动，拿到轮次后重新判断；
+    // 启动成功后才清除 idle_stopped，启动失败时下次活动会再次尝试
+    let _turn = lifecycle_turn(&app_handle).await;
+    let (is_primary, config) = {
+        let mut guard = lock_process(&state);
+        if !guard.idle_stopped() {
+            return Ok(guard.actual_port());
+        }
+        if guard.is_disabled() {
+            return Err("Python backend is disabled".to_string());
+        }
+        guard.record_lifecycle("restart", "activity after idle shutdown".to_string());
+        (guard.is_primary(), guard.config().clone())
+    };
+    
+    println!("[tauri] Activity after idle shutdown, restarting Python backend...");
+    let spawn_handle = app_handle.clone();
+    let (info, log_offset) = tauri::async_runtime::spawn_blocking(move || {
+        let log_offset = fs::metadata(get_python_log_path()).map(|m| m.len()).unwrap_or(0);
+        spawn_python_backend(spawn_handle, is_primary, StartReason::AutoRestart).map(|info| (info, log_offset))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.message)?;
+    lock_process(&state).set_idle_stopped(false);
+    
+    // 与首次启动使用同一就绪策略（http_poll / tcp_connect / stdout_marker / none）
+    if !wait_for_readiness(
+        &app_handle,
+        &config.readiness,
+        config.effective_readiness_path(),
+        config.connect_host(),
+        info.port,
+        log_offset,
+        config.startup_timeout_ms,
+    )
+    .await
+    {
+        return Err(format!("Backend not ready within {}ms", config.startup_timeout_ms));
+    }
+    Ok(info.port)
+}
+
+#[tauri::command]
+fn start_python(app_handle: tauri::AppHandle) -> Result<SpawnInfo, String> {
+    println!("[tauri] Starting Python backend...");
+    // 手动启动时检查是否是主实例
+    let is_primary = if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+        lock_process(&state).is_primary()
+    } else {
+        false
+    };
+    let _turn = lifecycle_turn_blocking(&app_handle);
+    spawn_python_backend(app_handle, is_primary, StartReason::UserCommand).map_err(|e| e.message)
+}
+
+/// 检查后端端口是否接受 TCP 连接（不要求 HTTP 路由已就绪）
+#[tauri::command]
+async fn check_port_connectable(app_handle: tauri::AppHandle) -> Result<ConnectResult, String> {
+    let (host, port) = {
+        let state = app_handle
+            .try_state::<Arc<Mutex<PythonProcess>>>()
+            .ok_or("State not found")?;
+        let guard = lock_process(&state);
+        (guard.config().connect_host().to_string(), guard.actual_port())
+    };
+    Ok(probe_tcp(&host, port, TCP_PROBE_TIMEOUT).await)
+}
+
+/// 获取当前实例使用的后端端口
+#[tauri::command]
+fn get_backend_port(app_handle: tauri::AppHandle) -> Result<u16, String> {
+    if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+        let guard = lock_process(&state);
+        Ok(guard.actual_port())
+    } else {
+        Err("State not found".to_string())
+    }
+}
+
+/// 后端进程状态
+#[derive(Debug, Clone, Serialize)]
+struct PythonStatus {
+    running: bool,
+    pid: Option<u32>,
+    port: u16,
+    is_primary: bool,
+    owns_backend: bool,
+    uptime_ms: Option<u64>,
+    start_reason: Option<StartReason>,
+    state: BackendState,
+}
+
+/// 锁因 panic 中毒时恢复进程状态，返回是否进行了恢复
+///
+/// 恢复会重置为没有后端的状态；`respawn` 为 true 时随后重新启动后端。
+#[tauri::command]
+async fn recover_process_state(app_handle: tauri::AppHandle, respawn: Option<bool>) -> Result<bool, String> {
+    let state = app_handle
+        .try_state::<Arc<Mutex<PythonProcess>>>()
+        .ok_or("State not found")?;
+    let recovered = state.is_poisoned();
+    let is_primary = lock_process(&state).is_primary();
+    if recovered {
+        println!("[tauri] Process state recovered");
+    }
+    
+    if respawn.unwrap_or(false) {
+        let _turn = lifecycle_turn(&app_handle).await;
+        let handle = app_handle.clone();
+        tauri::async_runtime::spawn_blocking(move || spawn_python_backend(handle, is_primary, StartReason::UserCommand))
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| e.message)?;
+    }
+    Ok(recovered)
+}
+
+/// 获取后端进程状态（包括启动原因）
+#[tauri::command]
+fn get_python_status(app_handle: tauri::AppHandle) -> Result<PythonStatus, String> {
+    let state = app_handle
+        .try_state::<Arc<Mutex<PythonProcess>>>()
+        .ok_or("State not found")?;
+    let mut guard = lock_process(&state);
+    Ok(PythonStatus {
+        running: guard.is_running(),
+        pid: guard.pid(),
+        port: guard.actual_port(),
+        is_primary: guard.is_primary(),
+        owns_backend: guard.owns_backend(),
+        uptime_ms: guard.uptime().map(|d| d.as_millis() as u64),
+        start_reason: guard.start_reason(),
+        state: guard.state(),
+    })
+}
+
+/// 获取最近一次启动所处的阶段（从未启动过时为 None）
+#[tauri::command]
+fn get_startup_progress(app_handle: tauri::AppHandle) -> Result<Option<StartupProgress>, String> {
+    let state = app_handle
+        .try_state::<Arc<Mutex<PythonProcess>>>()
+        .ok_or("State not found")?;
+    let guard = lock_process(&state);
+    Ok(guard.startup_progress())
+}
+
+/// 前端未主动查询时，多久检查一次进程是否已自行退出
+const STATE_REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 等待后端进入指定状态（stopped / starting / running / stopping），超时返回错误
+///
+/// 状态变化通过 watch 通道通知；进程自行退出不会主动上报，因此会定期检查一次进程是否还在。
+#[tauri::command]
+async fn wait_for_python_state(app_handle: tauri::AppHandle, target: String, timeout_ms: u64) -> Result<(), String> {
+    let target = BackendState::parse(&target)?;
+    let state = app_handle
+        .try_state::<Arc<Mutex<PythonProcess>>>()
+        .ok_or("State not found")?;
+    let mut rx = lock_process(&state).subscribe_state();
+    
+    let wait = async {
+        loop {
+            if *rx.borrow_and_update() == target {
+                return Ok(());
+            }
+            tokio::select! {
+                changed = rx.changed() => {
+                    if changed.is_err() {
+                        return Err("Backend state channel closed".to_string());
+                    }
+                }
+                _ = tokio::time::sleep(STATE_REFRESH_INTERVAL) => {
+                    lock_process(&state).is_running();
+                }
+            }
+        }
+    };
+    
+    let result = tokio::time::timeout(Duration::from_millis(timeout_ms), wait).await;
+    result.map_err(|_| format!("Backend did not reach state {:?} within {}ms", target, timeout_ms))?
+}
+
+#[tauri::command]
+fn get_instance_status(app_handle: tauri::AppHandle) -> Result<bool, String> {
+    if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+        let guard = lock_process(&state);
+        Ok(guard.is_primary())
+    } else {
+        Err("State not found".to_string())
+    }
+}
+
+#[tauri::command]
+fn get_python_config(app_handle: tauri::AppHandle) -> Result<PythonConfig, String> {
+    if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+        let guard = lock_process(&state);
+        Ok(guard.config().clone())
+    } else {
+        Err("State not found".to_string())
+    }
+}
+
+/// 获取后端会发送的全部事件及示例负载
+#[tauri::command]
+fn get_event_catalog() -> Vec<EventDescriptor> {
+    event_catalog()
+}
+
+/// 获取配置的 JSON Schema，供设置界面渲染表单和保存前校验
+#[tauri::command]
+fn get_config_schema() -> serde_json::Value {
+    PythonConfig::json_schema()
+}
+
+/// 配置字段的取值来源
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigSource {
+    /// 内置默认值
+    Default,
+    /// 扁平配置文件
+    File,
+    /// 配置文件中的 profile
+    Profile,
+    /// 环境变量覆盖（AESTIVUS_<字段名>）
+    Env,
+    /// 启动时自动检测（如 python_path）
+    Detected,
+    /// 运行时修改，与配置文件不一致
+    Runtime,
+}
+
+/// 生效配置及每个字段的来源
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectiveConfig {
+    pub config: PythonConfig,
+    /// 字段名 -> 来源
+    pub provenance: BTreeMap<String, ConfigSource>,
+    /// 字段名 -> 最后设置该字段的配置文件（只含来自文件的字段）
+    pub field_paths: BTreeMap<String, String>,
+    /// 配置文件路径（None 表示全部使用默认值）
+    pub path: Option<String>,
+    /// 生效的 profile 名
+    pub profile: Option<String>,
+}
+
+/// 获取分层合并后的生效配置，以及每个字段来自哪一层
+#[tauri::command]
+fn get_effective_config(app_handle: tauri::AppHandle) -> Result<EffectiveConfig, String> {
+    let (config, path) = {
+        let state = app_handle
+            .try_state::<Arc<Mutex<PythonProcess>>>()
+            .ok_or("State not found")?;
+        let guard = lock_process(&state);
+        (guard.config().clone(), guard.config_path().map(|p| p.to_string()))
+    };
+    
+    // 重新按加载顺序读取各配置文件，字段归属于最后设置它的文件，环境变量覆盖在最上层
+    let loaded = PythonConfig::load_detailed(None);
+    let profile = loaded.profile;
+    let env = config_env_overrides();
+    
+    let effective = serde_json::to_value(&config).map_err(|e| e.to_string())?;
+    let defaults = serde_json::to_value(PythonConfig::default()).map_err(|e| e.to_string())?;
+    
+    let mut provenance = BTreeMap::new();
+    let mut field_paths = BTreeMap::new();
+    for field in PythonConfig::known_fields() {
+        let current = &effective[field.as_str()];
+        let file_hit = loaded.layers.iter().rev().find_map(|(path, document)| {
+            layer_field(document, profile.as_deref(), &field).map(|value| (path, document, value))
+        });
+        let (layer, layer_value) = match (env.get(&field), file_hit) {
+            (Some(value), _) => (ConfigSource::Env, value),
+            (None, Some((path, document, value))) => {
+                field_paths.insert(field.clone(), path.clone());
+                let layer = if document.get("profiles").is_some() { ConfigSource::Profile } else { ConfigSource::File };
+                (layer, value)
+            }
+            (None, None) => (ConfigSource::Default, &defaults[field.as_str()]),
+        };
+        let source = if current == layer_value {
+            layer
+        } else if field == "python_path" && layer_value == "python" {
+            // "python" 是自动检测的占位值
+            ConfigSource::Detected
+        } else {
+            ConfigSource::Runtime
+        };
+        provenance.insert(field, source);
+    }
+    
+    Ok(EffectiveConfig { config, provenance, field_paths, path, profile })
+}
+
+/// 内存中的配置与配置文件不一致的字段
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigFieldDiff {
+    pub field: String,
+    pub on_disk: serde_json::Value,
+    pub in_memory: serde_json::Value,
+}
+
+/// 重新读取配置文件，逐字段对比内存中的配置，返回所有未保存的差异
+#[tauri::command]
+fn config_diff(app_handle: tauri::AppHandle) -> Result<Vec<ConfigFieldDiff>, String> {
+    let in_memory = {
+        let state = app_handle
+            .try_state::<Arc<Mutex<PythonProcess>>>()
+            .ok_or("State not found")?;
+        let guard = lock_process(&state);
+        serde_json::to_value(guard.config()).map_err(|e| e.to_string())?
+    };
+    let on_disk = serde_json::to_value(PythonConfig::load_detailed(None).config).map_err(|e| e.to_string())?;
+    
+    Ok(PythonConfig::known_fields()
+        .into_iter()
+        .filter(|field| on_disk[field.as_str()] != in_memory[field.as_str()])
+        .map(|field| ConfigFieldDiff {
+            on_disk: on_disk[field.as_str()].clone(),
+            in_memory: in_memory[field.as_str()].clone(),
+            field,
+        })
+        .collect())
+}
+
+/// 用默认值补全一份配置中缺失的字段，保留无法识别的字段
+fn fill_config_defaults(document: &serde_json::Value) -> Result<serde_json::Value, String> {
+    let config: PythonConfig = serde_json::from_value(document.clone()).map_err(|e| e.to_string())?;
+    let mut filled = serde_json::to_value(config).map_err(|e| e.to_string())?;
+    if let (Some(filled), Some(original)) = (filled.as_object_mut(), document.as_object()) {
+        for (key, value) in original {
+            filled.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+    }
+    Ok(filled)
+}
+
+/// 把当前配置文件升级到最新的字段集合：缺失字段按默认值写出，原文件备份为 .bak
+///
+/// 基于文件内容迁移（不写入运行时修改和自动检测的值）；含 profiles 时逐个补全。
+#[tauri::command]
+fn migrate_config(app_handle: tauri::AppHandle) -> Result<PathBuf, String> {
+    let path = {
+        let state = app_handle
+            .try_state::<Arc<Mutex<PythonProcess>>>()
+            .ok_or("State not found")?;
+        let guard = lock_process(&state);
+        guard
+            .config_path()
+            .map(|p| p.to_string())
+            .ok_or("No config file loaded, nothing to migrate")?
+    };
+    
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let mut value = parse_config_document(&path, &content)?;
+    match value.get_mut("profiles").and_then(|p| p.as_object_mut()) {
+        Some(profiles) => {
+            for (name, profile) in profiles.iter_mut() {
+                *profile = fill_config_defaults(profile).map_err(|e| format!("Profile '{}': {}", name, e))?;
+            }
+        }
+        None => value = fill_config_defaults(&value)?,
+    }
+    let migrated = serialize_config_document(&path, &value)?;
+    
+    let backup = format!("{}.bak", path);
+    fs::copy(&path, &backup).map_err(|e| format!("Failed to back up {} to {}: {}", path, backup, e))?;
+    fs::write(&path, migrated).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    println!("[tauri] Migrated config {} (backup: {})", path, backup);
+    Ok(PathBuf::from(path))
+}
+
+/// 获取加载配置时产生的警告
+#[tauri::command]
+fn get_config_warnings(app_handle: tauri::AppHandle) -> Result<Vec<ConfigWarning>, String> {
+    if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+        let guard = lock_process(&state);
+        Ok(guard.config_warnings().to_vec())
+    } else {
+        Err("State not found".to_string())
+    }
+}
+
+/// 切换配置文件中生效的 profile，可选立即重启后端
+#[tauri::command]
+fn set_active_profile(app_handle: tauri::AppHandle, name: String, restart: bool) -> Result<PythonConfig, String> {
+    let config_path = {
+        let state = app_handle
+            .try_state::<Arc<Mutex<PythonProcess>>>()
+            .ok_or("State not found")?;
+        let guard = lock_process(&state);
+        guard
+            .config_path()
+            .map(|p| p.to_string())
+            .ok_or("No config file loaded, profiles are unavailable")?
+    };
+    
+    let content = fs::read_to_string(&config_path)
+        .map_err(|e| format!("Failed to read {}: {}", config_path, e))?;
+    let mut value = parse_config_document(&config_path, &content)?;
+    if value.get("profiles").is_none() {
+        return Err(format!("{} has no \"profiles\" section", config_path));
+    }
+    
+    // 写入前先确认 profile 存在且有效
+    PythonConfig::from_document(value.clone(), Some(&name))?;
+    
+    // 持久化 active 字段；先记下写入内容，配置监听收到这次写入时不会当作外部修改
+    value["active"] = serde_json::Value::String(name.clone());
+    let updated = serialize_config_document(&config_path, &value)?;
+    let state = app_handle
+        .try_state::<Arc<Mutex<PythonProcess>>>()
+        .ok_or("State not found")?;
+    lock_process(&state).set_persisted_content(updated.clone());
+    fs::write(&config_path, updated).map_err(|e| format!("Failed to write {}: {}", config_path, e))?;
+    println!("[tauri] Active profile set to '{}' in {}", name, config_path);
+    
+    // 按完整的加载流程（含多文件合并）重新读取，来源路径和警告一并更新
+    let loaded = PythonConfig::load_detailed(Some(&name));
+    let config = loaded.config;
+    let is_primary = {
+        let mut guard = lock_process(&state);
+        guard.set_config(config.clone());
+        guard.set_config_path(loaded.path);
+        guard.set_config_warnings(loaded.warnings);
+        guard.set_configured_python_path(loaded.configured_python_path);
+        guard.is_primary()
+    };
+    
+    if restart {
+        let _turn = lifecycle_turn_blocking(&app_handle);
+        cleanup_python_process(&app_handle);
+        spawn_python_backend(app_handle, is_primary, StartReason::ConfigReload).map_err(|e| e.message)?;
+    }
+    
+    Ok(config)
+}
+
+/// 将配置写回配置文件并更新内存中的配置，返回写入的路径
+/// profiles 格式写入当前 active profile，扁平格式直接覆盖；未加载过配置文件时写入 config/python.json
+fn persist_python_config(app_handle: &tauri::AppHandle, config: &PythonConfig) -> Result<String, String> {
+    let state = app_handle
+        .try_state::<Arc<Mutex<PythonProcess>>>()
+        .ok_or("State not found")?;
+    let mut guard = lock_process(&state);
+    let path = guard.config_path().unwrap_or(DEFAULT_CONFIG_PATH).to_string();
+    
+    // 只写用户配置的值：python_path 与运行时解析出的解释器相同（占位符自动检测、venv_path、
+    // 启动时回退）时写回配置文件中原来的值；端口的运行时结果在 actual_port，不在配置里
+    let mut written = config.clone();
+    if let Some(configured) = guard.configured_python_path.clone() {
+        if written.python_path == guard.config().python_path {
+            written.python_path = configured;
+        }
+    }
+    let existing = fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| parse_config_document(&path, &content).ok());
+    let mut config_value = serde_json::to_value(&written).map_err(|e| e.to_string())?;
+    // 环境变量覆盖且未修改的字段不写入文件：保留文件原值，文件中没有则省略
+    let on_disk = existing
+        .clone()
+        .and_then(|existing| select_profile_document(existing, None).ok())
+        .map(|(document, _, _)| document);
+    let current = serde_json::to_value(guard.config()).map_err(|e| e.to_string())?;
+    if let Some(fields) = config_value.as_object_mut() {
+        for field in config_env_overrides().keys() {
+            if fields.get(field) != current.get(field) {
+                continue;
+            }
+            match on_disk.as_ref().and_then(|document| document.get(field)) {
+                Some(value) => fields.insert(field.clone(), value.clone()),
+                None => fields.remove(field),
+            };
+        }
+    }
+    let value = match existing {
+        Some(mut existing) if existing.get("profiles").is_some() => {
+            let active = existing
+                .get("active")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+                .or_else(|| existing["profiles"].as_object().and_then(|p| p.keys().next().cloned()))
+                .unwrap_or_else(|| "default".to_string());
+            existing["profiles"][active.as_str()] = config_value;
+            existing
+        }
+        _ => config_value,
+    };
+    
+    if let Some(parent) = std::path::Path::new(&path).parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let content = serialize_config_document(&path, &value)?;
+    fs::write(&path, &content).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    
+    guard.set_persisted_content(content);
+    guard.set_configured_python_path(written.python_path);
+    guard.set_config(config.clone());
+    guard.set_config_path(Some(path.clone()));
+    println!("[tauri] Python config saved to {}", path);
+    Ok(path)
+}
+
+/// 保存 Python 配置到配置文件
+#[tauri::command]
+fn save_python_config(app_handle: tauri::AppHandle, config: PythonConfig) -> Result<String, String> {
+    config.validate()?;
+    persist_python_config(&app_handle, &config)
+}
+
+// 兼容旧 API
+#[tauri::command]
+fn shutdown_sidecar(app_handle: tauri::AppHandle) -> Result<String, String> {
+    shutdown_python(app_handle)
+}
+
+#[tauri::command]
+fn start_sidecar(app_handle: tauri::AppHandle) -> Result<String, String> {
+    // 旧调用方只接收字符串，需要 PID/端口时改用 start_python
+    let info = start_python(app_handle)?;
+    Ok(format!("Python backend started on port {}.", info.port))
+}
+
+/// 获取后端进程的生命周期历史（默认返回全部，按时间先后）
+#[tauri::command]
+fn get_python_lifecycle(app_handle: tauri::AppHandle, limit: Option<usize>) -> Result<Vec<LifecycleEvent>, String> {
+    let state = app_handle
+        .try_state::<Arc<Mutex<PythonProcess>>>()
+        .ok_or("State not found")?;
+    let guard = lock_process(&state);
+    Ok(guard.lifecycle(limit.unwrap_or(LIFECYCLE_HISTORY_LIMIT)))
+}
+
+/// 获取应用启动时是否自动启动后端
+#[tauri::command]
+fn get_auto_start(app_handle: tauri::AppHandle) -> Result<bool, String> {
+    let state = app_handle
+        .try_state::<Arc<Mutex<PythonProcess>>>()
+        .ok_or("State not found")?;
+    let guard = lock_process(&state);
+    Ok(guard.config().auto_start)
+}
+
+/// 设置应用启动时是否自动启动后端，并写入配置文件
+#[tauri::command]
+fn set_auto_start(app_handle: tauri::AppHandle, value: bool) -> Result<(), String> {
+    let config = {
+        let state = app_handle
+            .try_state::<Arc<Mutex<PythonProcess>>>()
+            .ok_or("State not found")?;
+        let guard = lock_process(&state);
+        PythonConfig { auto_start: value, ..guard.config().clone() }
+    };
+    persist_python_config(&app_handle, &config)?;
+    println!("[tauri] Backend auto start set to {}", value);
+    Ok(())
+}
+
+/// 检查解释器能否导入指定模块
+#[tauri::command]
+fn check_python_imports(python_path: String, modules: Vec<String>) -> Vec<ImportResult> {
+    if modules.is_empty() {
+        return Vec::new();
+    }
+    
+    let failure = |error: String| -> Vec<ImportResult> {
+        modules
+            .iter()
+            .map(|module| ImportResult { module: module.clone(), ok: false, error: Some(error.clone()) })
+            .collect()
+    };
+    
+    let output = match Command::new(&python_path)
+        .arg("-c")
+        .arg(IMPORT_CHECK_SCRIPT)
+        .args(&modules)
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) => return failure(format!("Failed to run '{}': {}", python_path, e)),
+    };
+    
+    // 被导入的模块可能自己打印内容，结果在最后一行
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match stdout
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .and_then(|line| serde_json::from_str::<Vec<ImportResult>>(line).ok())
+    {
+        Some(results) => results,
+        None => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            failure(format!("Import check failed: {}", stderr.trim()))
+        }
+    }
+}
+
+/// 一次性 Python 脚本的执行结果
+#[derive(Debug, Clone, Serialize)]
+struct ScriptResult {
+    /// 退出码（被信号终止或超时被杀时为 None）
+    exit_code: Option<i32>,
+    stdout: String,
+    stderr: String,
+    /// 是否因超时被终止
+    timed_out: bool,
+}
+
+/// 用当前配置的解释器运行一次性脚本（迁移、数据检查等），等待结束并返回输出
+///
+/// 与受管后端进程互不影响；指定 timeout_ms 时超时会杀掉脚本进程。
+#[tauri::command]
+async fn run_python_script(
+    app_handle: tauri::AppHandle,
+    args: Vec<String>,
+    timeout_ms: Option<u64>,
+) -> Result<ScriptResult, String> {
+    let (launcher, python_path) = {
+        let state = app_handle
+            .try_state::<Arc<Mutex<PythonProcess>>>()
+            .ok_or("State not found")?;
+        let guard = lock_process(&state);
+        let config = guard.config();
+        (config.launcher.clone().unwrap_or_default(), config.python_path.clone())
+    };
+    
+    tauri::async_runtime::spawn_blocking(move || -> Result<ScriptResult, String> {
+        use std::io::Read;
+        use std::process::Stdio;
+        
+        println!("[tauri] Running Python script: {} {}", python_path, args.join(" "));
+        let mut child = python_command(&launcher, &python_path)
+            .args(&args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run '{}': {}", python_path, e))?;
+        
+        // 在独立线程读取输出，避免管道写满导致脚本阻塞
+        let read_pipe = |pipe: Option<Box<dyn Read + Send>>| {
+            std::thread::spawn(move || {
+                let mut buf = Vec::new();
+                if let Some(mut pipe) = pipe {
+                    let _ = pipe.read_to_end(&mut buf);
+                }
+                String::from_utf8_lossy(&buf).into_owned()
+            })
+        };
+        let stdout = read_pipe(child.stdout.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+        let stderr = read_pipe(child.stderr.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+        
+        let mut timed_out = false;
+        let status = match timeout_ms {
+            Some(timeout_ms) if !wait_for_exit(&mut child, timeout_ms) => {
+                println!("[tauri] Python script timed out after {}ms, killing", timeout_ms);
+                timed_out = true;
+                let _ = child.kill();
+                child.wait()
+            }
+            _ => child.wait(),
+        }
+        .map_err(|e| format!("Failed to wait for script: {}", e))?;
+        
+        Ok(ScriptResult {
+            exit_code: if timed_out { None } else { status.code() },
+            stdout: stdout.join().unwrap_or_default(),
+            stderr: stderr.join().unwrap_or_default(),
+            timed_out,
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 列出所有候选 Python 解释器及其状态
+#[tauri::command]
+fn list_python_candidates(app_handle: tauri::AppHandle) -> Vec<PythonCandidate> {
+    let module = app_handle
+        .try_state::<Arc<Mutex<PythonProcess>>>()
+        .map(|state| lock_process(&state).config().module.clone())
+        .unwrap_or_else(|| "aestiv".to_string());
+    
+    python_candidates()
+        .into_iter()
+        .map(|path| {
+            let version = probe_python_version(&[], path);
+            let available = version.is_some();
+            PythonCandidate {
+                path: path.to_string(),
+                version,
+                available,
+                aestiv_installed: available && check_aestiv_installed(path, &module),
+            }
+        })
+        .collect()
+}
+
+/// 获取 Python 后端日志文件路径
+#[tauri::command]
+fn get_python_log_file() -> Result<String, String> {
+    let path = get_python_log_path();
+    path.to_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| "Invalid path".to_string())
+}
+
+// ============== 后端 HTTP 命令 ==============
+
+/// 后端支持的日志级别
+const BACKEND_LOG_LEVELS: [&str; 5] = ["debug", "info", "warning", "error", "critical"];
+
+/// 获取当前后端的 HTTP 基础地址
+fn backend_base_url(app_handle: &tauri::AppHandle) -> Result<String, String> {
+    let state = app_handle
+        .try_state::<Arc<Mutex<PythonProcess>>>()
+        .ok_or("State not found")?;
+    let guard = lock_process(&state);
+    Ok(guard.config().connect_base_url(guard.actual_port()))
+}
+
+/// 后端 HTTP 响应
+#[derive(Debug, Clone)]
+struct BackendResponse {
+    status: u16,
+    body: String,
+}
+
+impl BackendResponse {
+    fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+    
+    /// 解析为 JSON，非 JSON 响应作为字符串返回
+    fn json(&self) -> serde_json::Value {
+        serde_json::from_str(&self.body)
+            .unwrap_or_else(|_| serde_json::Value::String(self.body.trim().to_string()))
+    }
+}
+
+/// 向当前后端发送 HTTP 请求，所有后端 HTTP 功能共用这一条路径
+/// URL 由配置的 host 和实际端口拼接；任何失败都以 Err 返回，不会 panic
+async fn backend_request(
+    app_handle: &tauri::AppHandle,
+    method: &str,
+    path: &str,
+    body: Option<&serde_json::Value>,
+) -> Result<BackendResponse, String> {
+    use tauri_plugin_http::reqwest;
+    
+    if !path.starts_with('/') {
+        return Err(format!("Backend path must start with '/', got '{}'", path));
+    }
+    let method = reqwest::Method::from_bytes(method.to_uppercase().as_bytes())
+        .map_err(|_| format!("Invalid HTTP method '{}'", method))?;
+    let url = format!("{}{}", backend_base_url(app_handle)?, path);
+    let auth_token = app_handle
+        .try_state::<Arc<Mutex<PythonProcess>>>()
+        .and_then(|state| lock_process(&state).config().auth_token.clone());
+    
+    let mut request = reqwest::Client::new().request(method, &url);
+    if let Some(token) = auth_token {
+        request = request.bearer_auth(token);
+    }
+    if let Some(body) = body {
+        request = request
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_string());
+    }
+    
+    let resp = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach backend: {}", e))?;
+    let status = resp.status().as_u16();
+    let body = resp
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+    Ok(BackendResponse { status, body })
+}
+
+/// 发送请求并要求 2xx 响应，返回解析后的 JSON
+/// 404 视为后端未提供该接口
+async fn backend_call(
+    app_handle: &tauri::AppHandle,
+    method: &str,
+    path: &str,
+    body: Option<&serde_json::Value>,
+) -> Result<serde_json::Value, String> {
+    let resp = backend_request(app_handle, method, path, body).await?;
+    if resp.status == 404 {
+        return Err(format!("Backend does not expose {}", path));
+    }
+    if !resp.is_success() {
+        return Err(format!("Backend returned {} for {}", resp.status, path));
+    }
+    Ok(resp.json())
+}
+
+/// 读取后端当前日志级别（需要后端提供 /loglevel 接口）
+#[tauri::command]
+async fn get_backend_log_level(app_handle: tauri::AppHandle) -> Result<String, String> {
+    let value = backend_call(&app_handle, "GET", "/loglevel", None).await?;
+    // 兼容 {"level": "info"} 和纯文本两种响应
+    let level = match &value {
+        serde_json::Value::String(level) => level.clone(),
+        other => other
+            .get("level")
+            .and_then(|l| l.as_str())
+            .map(|l| l.to_string())
+            .ok_or("Unexpected /loglevel response")?,
+    };
+    Ok(level)
+}
+
+/// 运行时修改后端日志级别（需要后端提供 /loglevel 接口）
+#[tauri::command]
+async fn set_backend_log_level(app_handle: tauri::AppHandle, level: String) -> Result<(), String> {
+    let level = level.to_lowercase();
+    if !BACKEND_LOG_LEVELS.contains(&level.as_str()) {
+        return Err(format!(
+            "Invalid log level '{}', expected one of: {}",
+            level,
+            BACKEND_LOG_LEVELS.join("|")
+        ));
+    }
+    
+    let body = serde_json::json!({ "level": level });
+    backend_call(&app_handle, "POST", "/loglevel", Some(&body)).await?;
+    println!("[tauri] Backend log level set to: {}", level);
+    Ok(())
+}
+
+/// 读取后端当前的并发工作数（需要后端提供 /workers 接口）
+#[tauri::command]
+async fn get_backend_workers(app_handle: tauri::AppHandle) -> Result<u32, String> {
+    let value = backend_call(&app_handle, "GET", "/workers", None).await?;
+    // 兼容 {"workers": 4} 和纯数字两种响应
+    value
+        .as_u64()
+        .or_else(|| value.get("workers").and_then(|w| w.as_u64()))
+        .map(|count| count.min(u32::MAX as u64) as u32)
+        .ok_or_else(|| "Unexpected /workers response".to_string())
+}
+
+/// 运行时调整后端并发工作数，无需重启（需要后端提供 /workers 接口）
+#[tauri::command]
+async fn set_backend_workers(app_handle: tauri::AppHandle, count: u32) -> Result<(), String> {
+    let max = match app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+        Some(state) => lock_process(&state).config().max_backend_workers,
+        None => return Err("State not found".to_string()),
+    };
+    if !(1..=max).contains(&count) {
+        return Err(format!("Invalid worker count {}, expected 1..={}", count, max));
+    }
+    
+    let body = serde_json::json!({ "workers": count });
+    backend_call(&app_handle, "POST", "/workers", Some(&body)).await?;
+    println!("[tauri] Backend workers set to: {}", count);
+    Ok(())
+}
+
+/// OpenAPI 规范中的一条路由
+#[derive(Debug, Clone, Serialize)]
+struct BackendRoute {
+    method: String,
+    path: String,
+    summary: Option<String>,
+}
+
+/// 查询后端在途请求数时依次尝试的接口
+const ACTIVE_REQUESTS_PATHS: [&str; 2] = ["/active_requests", "/status"];
+
+/// shutdown_python_if_idle 的结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+enum ShutdownOutcome {
+    /// 后端空闲，已正常关闭
+    Stopped,
+    /// 后端仍有在途请求，未关闭
+    Busy { active: u32 },
+    /// 后端本来就没有运行
+    NotRunning,
+}
+
+/// 从 /active_requests 或 /status 的响应中读取在途请求数
+///
+/// 兼容纯数字以及 {"active_requests": n} / {"active": n} 两种对象形式
+fn parse_active_requests(value: &serde_json::Value) -> Option<u32> {
+    let count = match value {
+        serde_json::Value::Number(n) => n.as_u64(),
+        other => ["active_requests", "active"]
+            .iter()
+            .find_map(|key| other.get(*key).and_then(|v| v.as_u64())),
+    }?;
+    Some(count.min(u32::MAX as u64) as u32)
+}
+
+/// 仅在后端没有在途请求时关闭后端，否则返回 Busy 且不做任何操作
+///
+/// 后端无法报告在途请求数时返回错误，不会贸然关闭；需要无条件关闭时使用 shutdown_python。
+#[tauri::command]
+async fn shutdown_python_if_idle(app_handle: tauri::AppHandle) -> Result<ShutdownOutcome, String> {
+    // 检查与关闭之间不允许插入其他生命周期操作
+    let _turn = lifecycle_turn(&app_handle).await;
+    {
+        let state = app_handle
+            .try_state::<Arc<Mutex<PythonProcess>>>()
+            .ok_or("State not found")?;
+        let mut guard = lock_process(&state);
+        if !guard.is_running() {
+            return Ok(ShutdownOutcome::NotRunning);
+        }
+    }
+    
+    let mut last_error = None;
+    let mut active = None;
+    for path in ACTIVE_REQUESTS_PATHS {
+        match backend_call(&app_handle, "GET", path, None).await {
+            Ok(value) => match parse_active_requests(&value) {
+                Some(count) => {
+                    active = Some(count);
+                    break;
+                }
+                None => last_error = Some(format!("Unexpected {} response", path)),
+            },
+            Err(e) => last_error = Some(e),
+        }
+    }
+    let active = active.ok_or_else(|| {
+        format!(
+            "Cannot tell whether the backend is idle ({}); not shutting down",
+            last_error.unwrap_or_default()
+        )
+    })?;
+    
+    if active > 0 {
+        println!("[tauri] Backend busy ({} active requests), skipping shutdown", active);
+        return Ok(ShutdownOutcome::Busy { active });
+    }
+    
+    println!("[tauri] Backend idle, shutting down Python backend...");
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn_blocking(move || cleanup_python_process(&handle))
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(ShutdownOutcome::Stopped)
+}
+
+/// 读取后端的 OpenAPI 规范
+#[tauri::command]
+async fn get_backend_openapi(app_handle: tauri::AppHandle) -> Result<serde_json::Value, String> {
+    let path = {
+        let state = app_handle
+            .try_state::<Arc<Mutex<PythonProcess>>>()
+            .ok_or("State not found")?;
+        let guard = lock_process(&state);
+        guard.config().openapi_path.clone()
+    };
+    let spec = backend_call(&app_handle, "GET", &path, None).await?;
+    if spec.get("paths").is_none() {
+        return Err(format!("{} did not return an OpenAPI document", path));
+    }
+    Ok(spec)
+}
+
+/// 从 OpenAPI 规范中列出后端提供的路由（按路径、方法排序）
+#[tauri::command]
+async fn list_backend_routes(app_handle: tauri::AppHandle) -> Result<Vec<BackendRoute>, String> {
+    const METHODS: [&str; 8] = ["get", "put", "post", "delete", "options", "head", "patch", "trace"];
+    
+    let spec = get_backend_openapi(app_handle).await?;
+    let mut routes = Vec::new();
+    if let Some(paths) = spec["paths"].as_object() {
+        for (path, item) in paths {
+            for method in METHODS {
+                if let Some(operation) = item.get(method) {
+                    routes.push(BackendRoute {
+                        method: method.to_uppercase(),
+                        path: path.clone(),
+                        summary: operation.get("summary").and_then(|s| s.as_str()).map(|s| s.to_string()),
+                    });
+                }
+            }
+        }
+    }
+    routes.sort_by(|a, b| a.path.cmp(&b.path).then_with(|| a.method.cmp(&b.method)));
+    Ok(routes)
+}
+
+/// 在默认浏览器中打开后端 API 文档
+#[tauri::command]
+fn open_backend_docs(app_handle: tauri::AppHandle) -> Result<(), String> {
+    use tauri_plugin_opener::OpenerExt;
+    
+    let (url, host, port) = {
+        let state = app_handle
+            .try_state::<Arc<Mutex<PythonProcess>>>()
+            .ok_or("State not found")?;
+        let guard = lock_process(&state);
+        let config = guard.config();
+        (
+            format!("{}{}", config.connect_base_url(guard.actual_port()), config.docs_path),
+            config.connect_host().to_string(),
+            guard.actual_port(),
+        )
+    };
+    
+    if !is_port_in_use(&host, port) {
+        return Err(format!("Backend is not running on port {}", port));
+    }
+    
+    println!("[tauri] Opening backend docs: {}", url);
+    app_handle
+        .opener()
+        .open_url(url, None::<&str>)
+        .map_err(|e| format!("Failed to open browser: {}", e))
+}
+
+// ============== 诊断 ==============
+
+/// 诊断包中保留的日志行数
+const DIAGNOSTICS_LOG_LINES: usize = 200;
+
+/// 判断环境变量名是否可能包含敏感信息
+fn is_secret_env_key(key: &str) -> bool {
+    let key = key.to_uppercase();
+    ["TOKEN", "SECRET", "PASSWORD", "PASSWD", "CREDENTIAL", "PRIVATE", "API_KEY", "APIKEY", "AUTH"]
+        .iter()
+        .any(|marker| key.contains(marker))
+        || key.ends_with("_KEY")
+}
+
+/// 诊断包中记录的环境变量：PATH、PYTHON* 和虚拟环境相关变量
+fn is_diagnostics_env_key(key: &str) -> bool {
+    let key = key.to_uppercase();
+    key == "PATH" || key == "VIRTUAL_ENV" || key == "CONDA_PREFIX" || key.starts_with("PYTHON")
+}
+
+/// 读取后端日志文件的最后若干行
+fn read_log_tail(max_lines: usize) -> Vec<String> {
+    let content = match fs::read(get_python_log_path()) {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        Err(_) => return Vec::new(),
+    };
+    let lines: Vec<&str> = content.lines().collect();
+    let start = lines.len().saturating_sub(max_lines);
+    lines[start..].iter().map(|l| l.to_string()).collect()
+}
+
+/// 日志文件中的一行
+#[derive(Debug, Clone, Serialize)]
+struct LogLine {
+    /// 行号（从 1 开始）
+    line_number: usize,
+    /// 识别出的日志级别；traceback 等续行沿用上一行的级别
+    level: Option<&'static str>,
+    text: String,
+}
+
+/// 从行首附近的大写单词识别日志级别（兼容 uvicorn 的 "INFO:" 和 logging 的 " - ERROR - " 格式）
+fn detect_log_level(line: &str) -> Option<&'static str> {
+    line.split(|c: char| !c.is_ascii_alphabetic())
+        .filter(|token| !token.is_empty())
+        .take(6)
+        .find_map(|token| match token {
+            "DEBUG" => Some("debug"),
+            "INFO" => Some("info"),
+            "WARNING" | "WARN" => Some("warning"),
+            "ERROR" => Some("error"),
+            "CRITICAL" | "FATAL" => Some("critical"),
+            _ => None,
+        })
+}
+
+/// 日志级别在 BACKEND_LOG_LEVELS 中的序号（不区分大小写），未知级别返回错误
+fn log_level_rank(level: &str) -> Result<usize, String> {
+    let level = level.to_lowercase();
+    BACKEND_LOG_LEVELS
+        .iter()
+        .position(|known| *known == level)
+        .ok_or_else(|| format!(
+            "Invalid log level '{}', expected one of: {}",
+            level,
+            BACKEND_LOG_LEVELS.join("|")
+        ))
+}
+
+/// 按最低级别和关键字筛选后端日志文件，返回最近的 `limit` 行
+#[tauri::command]
+fn query_python_logs(min_level: String, contains: Option<String>, limit: usize) -> Result<Vec<LogLine>, String> {
+    let min_rank = log_level_rank(&min_level)?;
+    let needle = contains.map(|c| c.to_lowercase()).filter(|c| !c.is_empty());
+    
+    let content = match fs::read(get_python_log_path()) {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        Err(_) => return Ok(Vec::new()),
+    };
+    
+    let mut current_level = None;
+    let mut matches: Vec<LogLine> = Vec::new();
+    for (index, text) in content.lines().enumerate() {
+        if let Some(level) = detect_log_level(text) {
+            current_level = Some(level);
+        }
+        // 无法识别级别的行只在 min_level 为最低级别时返回
+        let rank = current_level.and_then(|level| BACKEND_LOG_LEVELS.iter().position(|l| *l == level));
+        if rank.unwrap_or(0) < min_rank {
+            continue;
+        }
+        if let Some(needle) = &needle {
+            if !text.to_lowercase().contains(needle.as_str()) {
+                continue;
+            }
+        }
+        matches.push(LogLine { line_number: index + 1, level: current_level, text: text.to_string() });
+    }
+    
+    let start = matches.len().saturating_sub(limit);
+    Ok(matches.split_off(start))
+}
+
+/// 收集诊断信息（配置、Python 环境、进程状态、日志、系统信息）
+fn collect_diagnostics(app_handle: &tauri::AppHandle) -> Result<serde_json::Value, String> {
+    let (config, config_path, config_warnings, process_status) = {
+        let state = app_handle
+            .try_state::<Arc<Mutex<PythonProcess>>>()
+            .ok_or("State not found")?;
+        let mut guard = lock_process(&state);
+        let process_status = serde_json::json!({
+            "running": guard.is_running(),
+            "pid": guard.pid(),
+            "port": guard.actual_port(),
+            "is_primary": guard.is_primary(),
+            "owns_backend": guard.owns_backend(),
+        });
+        (
+            guard.config().clone(),
+            guard.config_path().map(|p| p.to_string()),
+            guard.config_warnings().to_vec(),
+            process_status,
+        )
+    };
+    
+    let launcher = config.launcher.clone().unwrap_or_default();
+    let python_version = probe_python_version(&launcher, &config.python_path);
+    let package_status = if let Err(error) = interpreter_for_path(&config.python_path) {
+        serde_json::json!({ "code": "PYTHON_PATH_IS_DIRECTORY", "error": error })
+    } else if python_version.is_none() {
+        serde_json::json!({ "code": "PYTHON_NOT_FOUND" })
+    } else {
+        match check_aestiv_package(&launcher, &config.python_path, &config.module) {
+            PackageStatus::Installed => serde_json::json!({ "code": "INSTALLED" }),
+            PackageStatus::NotInstalled => serde_json::json!({ "code": "PACKAGE_NOT_INSTALLED" }),
+            PackageStatus::Broken(error) => serde_json::json!({ "code": "PACKAGE_BROKEN", "error": error }),
+        }
+    };
+    
+    // 只保留影响解释器查找的变量，其余环境可能含有与问题无关的敏感信息
+    let env: serde_json::Map<String, serde_json::Value> = std::env::vars()
+        .filter(|(key, _)| is_diagnostics_env_key(key))
+        .map(|(key, value)| {
+            let value = if is_secret_env_key(&key) { "<redacted>".to_string() } else { value };
+            (key, serde_json::Value::String(value))
+        })
+        .collect();
+    
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    
+    let mut config_value = serde_json::to_value(&config).map_err(|e| e.to_string())?;
+    if config.auth_token.is_some() {
+        config_value["auth_token"] = serde_json::json!("<redacted>");
+    }
+    for key in config.env.keys().filter(|key| is_secret_env_key(key)) {
+        config_value["env"][key] = serde_json::json!("<redacted>");
+    }
+    
+    let report = serde_json::json!({
+        "generated_at": timestamp,
+        "app_version": app_handle.package_info().version.to_string(),
+        "os": {
+            "os": std::env::consts::OS,
+            "family": std::env::consts::FAMILY,
+            "arch": std::env::consts::ARCH,
+        },
+        "config": config_value,
+        "config_path": config_path,
+        "config_warnings": config_warnings,
+        "python": {
+            "path": config.python_path,
+            "version": python_version,
+            "detected_path": detect_python_path(),
+            "package": package_status,
+        },
+        "process": process_status,
+        "log_file": get_python_log_path(),
+        "log_tail": read_log_tail(DIAGNOSTICS_LOG_LINES),
+        "env": env,
+    });
+    Ok(report)
+}
+
+/// 导出诊断包到临时目录，返回文件路径
+#[tauri::command]
+fn export_diagnostics(app_handle: tauri::AppHandle) -> Result<PathBuf, String> {
+    let report = collect_diagnostics(&app_handle)?;
+    let timestamp = report["generated_at"].as_u64().unwrap_or(0);
+    let path = std::env::temp_dir().join(format!("aestivus-diagnostics-{}.json", timestamp));
+    let content = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write diagnostics: {}", e))?;
+    println!("[tauri] Diagnostics exported to {:?}", path);
+    Ok(path)
+}
+
+/// 复制文本到剪贴板（如错误信息）
+#[tauri::command]
+fn copy_to_clipboard(app_handle: tauri::AppHandle, text: String) -> Result<(), String> {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+    
+    app_handle
+        .clipboard()
+        .write_text(text)
+        .map_err(|e| format!("Failed to write clipboard: {}", e))
+}
+
+/// 收集诊断信息并以 JSON 复制到剪贴板
+#[tauri::command]
+fn copy_diagnostics(app_handle: tauri::AppHandle) -> Result<(), String> {
+    let report = collect_diagnostics(&app_handle)?;
+    let content = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+    copy_to_clipboard(app_handle, content)?;
+    println!("[tauri] Diagnostics copied to clipboard");
+    Ok(())
+}
+
+/// 后端自检的超时时间（毫秒）
+const SELFTEST_TIMEOUT_MS: u64 = 60_000;
+
+/// 单项自检结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SelfTestCase {
+    name: String,
+    #[serde(alias = "ok")]
+    passed: bool,
+    #[serde(default, alias = "error")]
+    message: Option<String>,
+}
+
+/// 后端自检报告
+#[derive(Debug, Clone, Serialize)]
+struct SelfTestReport {
+    /// 所有检查项都通过且进程正常退出
+    passed: bool,
+    results: Vec<SelfTestCase>,
+    exit_code: Option<i32>,
+    /// 自检进程的 stderr，检查失败时便于排查
+    stderr: String,
+}
+
+/// 从自检输出的最后一行 JSON 中解析检查项
+///
+/// 兼容检查项数组以及 {"results": [...]} 两种形式
+fn parse_selftest_output(stdout: &str) -> Option<Vec<SelfTestCase>> {
+    let line = stdout.lines().rev().find(|line| !line.trim().is_empty())?;
+    let value: serde_json::Value = serde_json::from_str(line).ok()?;
+    let results = match value {
+        serde_json::Value::Array(_) => value,
+        other => other.get("results")?.clone(),
+    };
+    serde_json::from_value(results).ok()
+}
+
+/// 运行后端自带的自检（`python -m <module> --selftest`），返回各检查项的结果
+///
+/// 比 check_aestiv_installed 的"能否导入"更进一步，要求后端以 JSON 输出检查结果。
+#[tauri::command]
+async fn run_aestiv_selftest(app_handle: tauri::AppHandle) -> Result<SelfTestReport, String> {
+    let module = {
+        let state = app_handle
+            .try_state::<Arc<Mutex<PythonProcess>>>()
+            .ok_or("State not found")?;
+        let guard = lock_process(&state);
+        guard.config().module.clone()
+    };
+    
+    let args = vec!["-m".to_string(), module.clone(), "--selftest".to_string()];
+    let output = run_python_script(app_handle, args, Some(SELFTEST_TIMEOUT_MS)).await?;
+    if output.timed_out {
+        return Err(format!("{} --selftest did not finish within {}ms", module, SELFTEST_TIMEOUT_MS));
+    }
+    
+    let results = parse_selftest_output(&output.stdout).ok_or_else(|| {
+        let stderr = output.stderr.trim();
+        format!(
+            "{} --selftest produced no JSON results (exit code {:?}){}{}",
+            module,
+            output.exit_code,
+            if stderr.is_empty() { "" } else { ": " },
+            stderr.lines().last().unwrap_or("")
+        )
+    })?;
+    
+    let passed = output.exit_code == Some(0) && results.iter().all(|case| case.passed);
+    println!(
+        "[tauri] Selftest {}: {}/{} checks passed",
+        if passed { "passed" } else { "failed" },
+        results.iter().filter(|case| case.passed).count(),
+        results.len()
+    );
+    Ok(SelfTestReport {
+        passed,
+        results,
+        exit_code: output.exit_code,
+        stderr: output.stderr,
+    })
+}
+
+/// 日志捕获时轮询后端日志文件的间隔
+const LOG_CAPTURE_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 正在进行的日志捕获
+struct ActiveLogCapture {
+    path: PathBuf,
+    stop: Arc<tokio::sync::Notify>,
+    task: tauri::async_runtime::JoinHandle<()>,
+}
+
+/// 同一时间只允许一个日志捕获
+#[derive(Default)]
+struct LogCaptureState(Mutex<Option<ActiveLogCapture>>);
+
+/// 把一条捕获记录写成一行 JSON
+fn write_capture_record(file: &mut fs::File, source: &str, name: &str, payload: serde_json::Value) {
+    use std::io::Write;
+    
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let record = serde_json::json!({
+        "timestamp": timestamp,
+        "source": source,
+        "name": name,
+        "payload": payload,
+    });
+    let _ = writeln!(file, "{}", record);
+}
+
+/// 捕获任务：记录所有目录内的事件和后端日志文件新增的内容，直到超时或被停止
+async fn run_log_capture(
+    app_handle: tauri::AppHandle,
+    mut file: fs::File,
+    duration_ms: u64,
+    stop: Arc<tokio::sync::Notify>,
+) {
+    use tauri::Listener;
+    
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<(&'static str, String)>();
+    let listeners: Vec<_> = event_catalog()
+        .into_iter()
+        .map(|descriptor| {
+            let tx = tx.clone();
+            let name = descriptor.name;
+            app_handle.listen(name, move |event| {
+                let _ = tx.send((name, event.payload().to_string()));
+            })
+        })
+        .collect();
+    drop(tx);
+    
+    // Windows 上后端输出写入日志文件，只追加捕获开始之后的内容；
+    // 其他平台的输出在终端窗口中，无法捕获
+    let log_path = get_python_log_path();
+    let mut log_offset = fs::metadata(&log_path).map(|m| m.len()).unwrap_or(0);
+    let capture_log = cfg!(target_os = "windows");
+    
+    write_capture_record(&mut file, "capture", "started", serde_json::json!({ "duration_ms": duration_ms }));
+    let deadline = tokio::time::sleep(Duration::from_millis(duration_ms));
+    tokio::pin!(deadline);
+    let mut poll = tokio::time::interval(LOG_CAPTURE_POLL_INTERVAL);
+    let reason = loop {
+        tokio::select! {
+            _ = &mut deadline => break "timeout",
+            _ = stop.notified() => break "stopped",
+            Some((name, payload)) = rx.recv() => {
+                let payload = serde_json::from_str(&payload).unwrap_or(serde_json::Value::String(payload));
+                write_capture_record(&mut file, "event", name, payload);
+            }
+            _ = poll.tick(), if capture_log => {
+                use std::io::{Read, Seek, SeekFrom};
+                
+                let mut output = Vec::new();
+                if let Ok(mut log) = fs::File::open(&log_path) {
+                    if log.seek(SeekFrom::Start(log_offset)).is_ok() {
+                        let _ = log.read_to_end(&mut output);
+                    }
+                }
+                // 只写完整的行，未结束的行留到下次
+                if let Some(end) = output.iter().rposition(|&b| b == b'\n') {
+                    log_offset += end as u64 + 1;
+                    for line in String::from_utf8_lossy(&output[..end]).lines() {
+                        write_capture_record(&mut file, "log", "python_backend.log", serde_json::Value::String(line.to_string()));
+                    }
+                }
+            }
+        }
     };
     
-    let pid = child.id();
-    println!("[tauri] Python process spawned with PID: {} on port {} (in new console window)", pid, actual_port);
+    for id in listeners {
+        app_handle.unlisten(id);
+    }
+    while let Ok((name, payload)) = rx.try_recv() {
+        let payload = serde_json::from_str(&payload).unwrap_or(serde_json::Value::String(payload));
+        write_capture_record(&mut file, "event", name, payload);
+    }
+    write_capture_record(&mut file, "capture", "finished", serde_json::json!({ "reason": reason }));
+    let _ = file.sync_all();
+    println!("[tauri] Log capture finished ({})", reason);
     
-    // 存储进程和端口
-    if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
-        let mut process_state = state.lock().unwrap();
-        process_state.set_process(child);
-        process_state.set_actual_port(actual_port);
+    // 自然结束时清除状态；被 stop_log_capture 停止时状态已由它取走
+    if let Some(state) = app_handle.try_state::<LogCaptureState>() {
+        if let Ok(mut guard) = state.0.lock() {
+            if guard.as_ref().is_some_and(|capture| Arc::ptr_eq(&capture.stop, &stop)) {
+                guard.take();
+            }
+        }
+    }
+}
+
+/// 在指定时间内把后端事件和输出记录到文件（每行一个 JSON），用于附在问题报告中
+///
+/// 记录写入 `path`，`duration_ms` 后或调用 stop_log_capture 时结束。
+/// 后端输出只在 Windows 上可捕获（来自日志文件），其他平台只记录事件。
+#[tauri::command]
+fn start_log_capture(app_handle: tauri::AppHandle, path: PathBuf, duration_ms: u64) -> Result<(), String> {
+    let state = app_handle
+        .try_state::<LogCaptureState>()
+        .ok_or("State not found")?;
+    let mut guard = state.0.lock().map_err(|_| "Lock failed")?;
+    if let Some(active) = guard.as_ref() {
+        return Err(format!("A log capture is already running: {}", active.path.display()));
     }
     
-    let _ = app_handle.emit("python-ready", actual_port);
+    let file = fs::File::create(&path)
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let stop = Arc::new(tokio::sync::Notify::new());
+    let task = tauri::async_runtime::spawn(run_log_capture(app_handle.clone(), file, duration_ms, stop.clone()));
+    println!("[tauri] Capturing backend log to {} for {}ms", path.display(), duration_ms);
+    *guard = Some(ActiveLogCapture { path, stop, task });
+    Ok(())
+}
 
-    Ok(actual_port)
+/// 提前结束日志捕获，等待文件写完后返回其路径（没有进行中的捕获时为 None）
+#[tauri::command]
+async fn stop_log_capture(app_handle: tauri::AppHandle) -> Result<Option<PathBuf>, String> {
+    let active = {
+        let state = app_handle
+            .try_state::<LogCaptureState>()
+            .ok_or("State not found")?;
+        let mut guard = state.0.lock().map_err(|_| "Lock failed")?;
+        guard.take()
+    };
+    let Some(active) = active else {
+        return Ok(None);
+    };
+    active.stop.notify_one();
+    let _ = active.task.await;
+    Ok(Some(active.path))
 }
 
+// ============== 首次运行引导 ==============
 
+/// 用户选择的 Python 解释器信息
+#[derive(Debug, Clone, Serialize)]
+pub struct PythonInfo {
+    /// 解释器路径
+    pub path: String,
+    /// `--version` 输出
+    pub version: String,
+    /// 是否已安装后端包
+    pub aestiv_installed: bool,
+}
 
-#[tauri::command]
-fn shutdown_python(app_handle: tauri::AppHandle) -> Result<String, String> {
-    println!("[tauri] Shutting down Python backend...");
-    cleanup_python_process(&app_handle);
-    Ok("Python backend shutdown.".to_string())
+/// 检查解释器是否可用，并收集版本与后端包安装状态
+fn inspect_python(python_path: &str, module: &str) -> Result<PythonInfo, String> {
+    let version = probe_python_version(&[], python_path)
+        .ok_or_else(|| format!("'{}' is not a usable Python interpreter.", python_path))?;
+    Ok(PythonInfo {
+        path: python_path.to_string(),
+        version,
+        aestiv_installed: check_aestiv_installed(python_path, module),
+    })
 }
 
-#[tauri::command]
-fn start_python(app_handle: tauri::AppHandle) -> Result<String, String> {
-    println!("[tauri] Starting Python backend...");
-    // 手动启动时检查是否是主实例
-    let is_primary = if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
-        state.lock().unwrap().is_primary()
-    } else {
-        false
+/// 在内存配置上替换 python_path 并持久化
+fn apply_python_path(app_handle: &tauri::AppHandle, python_path: &str) -> Result<(), String> {
+    let config = {
+        let state = app_handle
+            .try_state::<Arc<Mutex<PythonProcess>>>()
+            .ok_or("State not found")?;
+        let guard = lock_process(&state);
+        PythonConfig {
+            python_path: python_path.to_string(),
+            ..guard.config().clone()
+        }
     };
-    let port = spawn_python_backend(app_handle, is_primary)?;
-    Ok(format!("Python backend started on port {}.", port))
+    persist_python_config(app_handle, &config)?;
+    println!("[tauri] Python interpreter set to: {}", python_path);
+    Ok(())
 }
 
-/// 获取当前实例使用的后端端口
+/// 通过文件选择框选择 Python 解释器，校验后写入配置
 #[tauri::command]
-fn get_backend_port(app_handle: tauri::AppHandle) -> Result<u16, String> {
-    if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
-        let guard = state.lock().map_err(|_| "Lock failed")?;
-        Ok(guard.actual_port())
-    } else {
-        Err("State not found".to_string())
+async fn pick_python_interpreter(app_handle: tauri::AppHandle) -> Result<PythonInfo, String> {
+    use tauri_plugin_dialog::DialogExt;
+    
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app_handle
+        .dialog()
+        .file()
+        .set_title("Select Python interpreter")
+        .pick_file(move |file| {
+            let _ = tx.send(file);
+        });
+    
+    let path = rx
+        .await
+        .map_err(|_| "Dialog closed unexpectedly".to_string())?
+        .ok_or("No file selected")?
+        .into_path()
+        .map_err(|e| format!("Invalid path: {}", e))?;
+    let python_path = path.to_string_lossy().to_string();
+    
+    let module = {
+        let state = app_handle
+            .try_state::<Arc<Mutex<PythonProcess>>>()
+            .ok_or("State not found")?;
+        let guard = lock_process(&state);
+        guard.config().module.clone()
+    };
+    
+    let info = inspect_python(&python_path, &module)?;
+    apply_python_path(&app_handle, &python_path)?;
+    Ok(info)
+}
+
+/// 启动失败时弹出原生对话框引导用户修复
+/// - 找不到 Python：选择解释器
+/// - 未安装后端包：用当前解释器执行 pip install
+fn show_setup_dialog(app_handle: &tauri::AppHandle, error: &BackendError) {
+    use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+    
+    let handle = app_handle.clone();
+    match error.code {
+        "PYTHON_NOT_FOUND" | "PYTHON_PATH_IS_DIRECTORY" => {
+            app_handle
+                .dialog()
+                .message(format!(
+                    "{}\n\nLocate a Python interpreter now, or ignore and configure it later in settings.",
+                    error.message
+                ))
+                .title("Python not found")
+                .kind(MessageDialogKind::Error)
+                .buttons(MessageDialogButtons::OkCancelCustom("Locate Python...".to_string(), "Ignore".to_string()))
+                .show(move |locate| {
+                    if locate {
+                        locate_python_interpreter(handle);
+                    }
+                });
+        }
+        "PACKAGE_NOT_INSTALLED" => {
+            app_handle
+                .dialog()
+                .message(format!(
+                    "{}\n\nInstall it into the configured interpreter now?",
+                    error.message
+                ))
+                .title("Backend package missing")
+                .kind(MessageDialogKind::Warning)
+                .buttons(MessageDialogButtons::OkCancelCustom("Install".to_string(), "Ignore".to_string()))
+                .show(move |install| {
+                    if install {
+                        install_backend_package(handle);
+                    }
+                });
+        }
+        "PACKAGE_BROKEN" => {
+            // 包本身已安装，重装无济于事；修好缺失的依赖后重试
+            app_handle
+                .dialog()
+                .message(format!(
+                    "{}\n\nInstall the missing dependency into the configured interpreter, then retry.",
+                    error.message
+                ))
+                .title("Backend package failed to import")
+                .kind(MessageDialogKind::Error)
+                .buttons(MessageDialogButtons::OkCancelCustom("Retry".to_string(), "Ignore".to_string()))
+                .show(move |retry| {
+                    if retry {
+                        retry_backend_startup(handle);
+                    }
+                });
+        }
+        "PORT_CONFLICT" => {
+            app_handle
+                .dialog()
+                .message(error.message.clone())
+                .title("Port in use")
+                .kind(MessageDialogKind::Warning)
+                .show(|_| {});
+        }
+        _ => {}
     }
 }
 
-#[tauri::command]
-fn get_instance_status(app_handle: tauri::AppHandle) -> Result<bool, String> {
-    if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
-        let guard = state.lock().map_err(|_| "Lock failed")?;
-        Ok(guard.is_primary())
-    } else {
-        Err("State not found".to_string())
+/// 打开文件选择框选择 Python 解释器，校验通过后保存并重新启动后端
+fn locate_python_interpreter(app_handle: tauri::AppHandle) {
+    use tauri_plugin_dialog::DialogExt;
+    
+    let handle = app_handle.clone();
+    app_handle
+        .dialog()
+        .file()
+        .set_title("Locate Python interpreter")
+        .pick_file(move |file| {
+            let Some(path) = file.and_then(|f| f.into_path().ok()) else {
+                return;
+            };
+            let python_path = path.to_string_lossy().to_string();
+            if !is_python_available(&[], &python_path) {
+                show_error_dialog(&handle, format!("'{}' is not a usable Python interpreter.", python_path));
+                return;
+            }
+            
+            if let Err(e) = apply_python_path(&handle, &python_path) {
+                println!("[tauri] Failed to persist python_path: {}", e);
+            }
+            retry_backend_startup(handle);
+        });
+}
+
+/// 用后端的解释器（launcher、venv_path 同样生效）安装 package_spec，然后重新启动后端
+fn install_backend_package(app_handle: tauri::AppHandle) {
+    let config = match app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+        Some(state) => lock_process(&state).config().clone(),
+        None => return,
+    };
+    
+    std::thread::spawn(move || {
+        println!("[tauri] Installing {} with {}...", config.package_spec, config.python_path);
+        // 与启动和升级使用同样的 launcher / venv 环境，装进后端实际运行的解释器
+        let launcher = config.launcher.clone().unwrap_or_default();
+        let result = python_command(&launcher, &config.python_path)
+            .args(["-m", "pip", "install"])
+            .args(config.package_spec.split_whitespace())
+            .output();
+        match result {
+            Ok(output) if output.status.success() => retry_backend_startup(app_handle),
+            Ok(output) => {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                show_error_dialog(&app_handle, format!("pip install failed:\n{}", stderr.trim()));
+            }
+            Err(e) => show_error_dialog(&app_handle, format!("Failed to run pip: {}", e)),
+        }
+    });
+}
+
+/// 修复后重新尝试启动后端，仍然失败时继续引导
+fn retry_backend_startup(app_handle: tauri::AppHandle) {
+    let is_primary = match app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+        Some(state) => lock_process(&state).is_primary(),
+        None => false,
+    };
+    let _turn = lifecycle_turn_blocking(&app_handle);
+    match spawn_python_backend(app_handle.clone(), is_primary, StartReason::UserCommand) {
+        Ok(info) => println!("[tauri] Python backend ready on port {}", info.port),
+        Err(e) => show_setup_dialog(&app_handle, &e),
     }
 }
 
-#[tauri::command]
-fn get_python_config(app_handle: tauri::AppHandle) -> Result<PythonConfig, String> {
-    if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
-        let guard = state.lock().map_err(|_| "Lock failed")?;
-        Ok(guard.config().clone())
-    } else {
-        Err("State not found".to_string())
+fn show_error_dialog(app_handle: &tauri::AppHandle, message: String) {
+    use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+    
+    app_handle
+        .dialog()
+        .message(message)
+        .title("Aestivus")
+        .kind(MessageDialogKind::Error)
+        .show(|_| {});
+}
+
+// ============== 启动画面 ==============
+
+const SPLASH_WINDOW: &str = "splash";
+/// 启动画面按钮通过导航到该 scheme 通知后端（导航本身会被拦截）
+const SPLASH_ACTION_SCHEME: &str = "aestivus-splash";
+
+/// 把启动进度（python-startup-progress 的 JSON 负载）推送到启动画面
+fn update_splash(window: &tauri::WebviewWindow, payload: &str) {
+    let _ = window.eval(&format!("window.aestivusSplash && window.aestivusSplash.update({})", payload));
+}
+
+/// 关闭启动画面并显示主窗口
+fn close_splash(app_handle: &tauri::AppHandle) {
+    if let Some(splash) = app_handle.get_webview_window(SPLASH_WINDOW) {
+        let _ = splash.close();
+    }
+    if let Some(main) = app_handle.get_webview_window("main") {
+        let _ = main.show();
+        let _ = main.set_focus();
     }
 }
 
-// 兼容旧 API
-#[tauri::command]
-fn shutdown_sidecar(app_handle: tauri::AppHandle) -> Result<String, String> {
-    shutdown_python(app_handle)
+/// 隐藏主窗口并显示启动画面，跟随 python-startup-progress 更新，
+/// 就绪后自动关闭；失败时保留画面并提供重试
+fn show_splash(app: &tauri::App) -> tauri::Result<()> {
+    use tauri::Listener;
+    use tauri::webview::PageLoadEvent;
+
+    let nav_handle = app.handle().clone();
+    tauri::WebviewWindowBuilder::new(app, SPLASH_WINDOW, WebviewUrl::App("splash.html".into()))
+        .title("Aestivus")
+        .inner_size(420.0, 280.0)
+        .resizable(false)
+        .decorations(false)
+        .center()
+        .on_page_load(|window, payload| {
+            // 页面加载前发出的进度会丢失，加载完成后补发当前阶段
+            if payload.event() != PageLoadEvent::Finished {
+                return;
+            }
+            let progress = window
+                .app_handle()
+                .try_state::<Arc<Mutex<PythonProcess>>>()
+                .and_then(|state| lock_process(&state).startup_progress());
+            if let Some(payload) = progress.and_then(|p| serde_json::to_string(&p).ok()) {
+                update_splash(&window, &payload);
+            }
+        })
+        .on_navigation(move |url| {
+            if url.scheme() != SPLASH_ACTION_SCHEME {
+                return true;
+            }
+            match url.host_str() {
+                Some("retry") => {
+                    println!("[tauri] Retrying backend startup from splash");
+                    let handle = nav_handle.clone();
+                    std::thread::spawn(move || retry_backend_startup(handle));
+                }
+                Some("dismiss") => close_splash(&nav_handle),
+                other => println!("[tauri] Unknown splash action: {:?}", other),
+            }
+            false
+        })
+        .build()?;
+
+    if let Some(main) = app.get_webview_window("main") {
+        let _ = main.hide();
+    }
+
+    // 启动画面关闭后（首次就绪或用户关闭）移除监听：之后的空闲拉起、配置重载、升级等
+    // 也会产生 ready 阶段，不能再把用户最小化或隐藏的主窗口拉到前台
+    let handle = app.handle().clone();
+    let listener = Arc::new(std::sync::OnceLock::new());
+    let own_listener = listener.clone();
+    let id = app.listen(events::STARTUP_PROGRESS, move |event| {
+        let stop_listening = || {
+            if let Some(id) = own_listener.get() {
+                handle.unlisten(*id);
+            }
+        };
+        let Some(window) = handle.get_webview_window(SPLASH_WINDOW) else {
+            stop_listening();
+            return;
+        };
+        let Ok(progress) = serde_json::from_str::<serde_json::Value>(event.payload()) else {
+            return;
+        };
+        if progress.get("phase").and_then(|p| p.as_str()) == Some("ready") {
+            close_splash(&handle);
+            stop_listening();
+        } else {
+            update_splash(&window, event.payload());
+        }
+    });
+    let _ = listener.set(id);
+    Ok(())
 }
 
-#[tauri::command]
-fn start_sidecar(app_handle: tauri::AppHandle) -> Result<String, String> {
-    start_python(app_handle)
+// ============== 后端升级 ==============
+
+/// 查询模块所属发行包版本的脚本，模块名通过 argv 传入
+const PACKAGE_VERSION_SCRIPT: &str = "import sys, importlib.metadata as m; \
+n = sys.argv[1]; \
+d = getattr(m, 'packages_distributions', dict)().get(n, [n]); \
+print(m.version(d[0]))";
+
+/// 读取后端包的已安装版本（按模块顶层名查找所属发行包，发行包名可能与模块名不同）
+fn installed_package_version(launcher: &[String], python_path: &str, package: &str) -> Option<String> {
+    let output = python_command(launcher, python_path)
+        .args(["-c", PACKAGE_VERSION_SCRIPT, package])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
-/// 获取 Python 后端日志文件路径
+/// 执行 pip 升级，逐行通过 python-upgrade-output 事件转发输出
+/// spec 为配置的 package_spec，按空白拆分为 pip 参数
+fn run_pip_upgrade(app_handle: &tauri::AppHandle, launcher: &[String], python_path: &str, spec: &str) -> Result<(), String> {
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+    
+    let mut child = python_command(launcher, python_path)
+        .args(["-m", "pip", "install", "--upgrade"])
+        .args(spec.split_whitespace())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run pip: {}", e))?;
+    
+    let stderr = child.stderr.take();
+    let stderr_handle = app_handle.clone();
+    let stderr_thread = std::thread::spawn(move || {
+        let mut tail = Vec::new();
+        if let Some(stderr) = stderr {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                let _ = stderr_handle.emit(events::UPGRADE_OUTPUT, serde_json::json!({ "stream": "stderr", "line": line }));
+                tail.push(line);
+            }
+        }
+        tail
+    });
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            let _ = app_handle.emit(events::UPGRADE_OUTPUT, serde_json::json!({ "stream": "stdout", "line": line }));
+        }
+    }
+    
+    let stderr_lines = stderr_thread.join().unwrap_or_default();
+    let status = child.wait().map_err(|e| format!("Failed to wait for pip: {}", e))?;
+    if !status.success() {
+        let start = stderr_lines.len().saturating_sub(20);
+        return Err(format!("pip install --upgrade {} failed ({}):\n{}", spec, status, stderr_lines[start..].join("\n")));
+    }
+    Ok(())
+}
+
+/// 升级后端包：停止后端 → pip install --upgrade {package_spec} → 校验可导入 → 重启并等待就绪
+///
+/// 升级或校验失败时后端保持停止状态，返回错误；成功时返回升级前后的版本。
 #[tauri::command]
-fn get_python_log_file() -> Result<String, String> {
-    let path = get_python_log_path();
-    path.to_str()
-        .map(|s| s.to_string())
-        .ok_or_else(|| "Invalid path".to_string())
+async fn upgrade_aestiv(app_handle: tauri::AppHandle) -> Result<String, String> {
+    let _turn = lifecycle_turn(&app_handle).await;
+    let (config, is_primary) = {
+        let state = app_handle
+            .try_state::<Arc<Mutex<PythonProcess>>>()
+            .ok_or("State not found")?;
+        let guard = lock_process(&state);
+        (guard.config().clone(), guard.is_primary())
+    };
+    let launcher = config.launcher.clone().unwrap_or_default();
+    let package = config.module.split('.').next().unwrap_or(&config.module).to_string();
+    
+    let handle = app_handle.clone();
+    let (before, log_offset) = tauri::async_runtime::spawn_blocking(move || -> Result<(Option<String>, u64), String> {
+        let before = installed_package_version(&launcher, &config.python_path, &package);
+        println!("[tauri] Upgrading {} (current: {})...", package, before.as_deref().unwrap_or("unknown"));
+        
+        cleanup_python_process(&handle);
+        run_pip_upgrade(&handle, &launcher, &config.python_path, &config.package_spec)?;
+        
+        if check_aestiv_package(&launcher, &config.python_path, &config.module) != PackageStatus::Installed {
+            return Err(format!("{} cannot be imported after the upgrade; backend left stopped", config.module));
+        }
+        
+        let log_offset = fs::metadata(get_python_log_path()).map(|m| m.len()).unwrap_or(0);
+        spawn_python_backend(handle, is_primary, StartReason::UserCommand).map_err(|e| format!("Upgrade succeeded but restart failed: {}", e.message))?;
+        Ok((before, log_offset))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    
+    let (config, port) = {
+        let state = app_handle
+            .try_state::<Arc<Mutex<PythonProcess>>>()
+            .ok_or("State not found")?;
+        let guard = lock_process(&state);
+        (guard.config().clone(), guard.actual_port())
+    };
+    let launcher = config.launcher.clone().unwrap_or_default();
+    let package = config.module.split('.').next().unwrap_or(&config.module).to_string();
+    
+    if !wait_for_readiness(
+        &app_handle,
+        &config.readiness,
+        config.effective_readiness_path(),
+        config.connect_host(),
+        port,
+        log_offset,
+        config.startup_timeout_ms,
+    )
+    .await
+    {
+        return Err(format!("Upgrade succeeded but the backend did not become ready within {}ms", config.startup_timeout_ms));
+    }
+    
+    let python_path = config.python_path.clone();
+    let version_package = package.clone();
+    let after = tauri::async_runtime::spawn_blocking(move || installed_package_version(&launcher, &python_path, &version_package))
+        .await
+        .ok()
+        .flatten();
+    let summary = format!(
+        "{} upgraded: {} -> {}",
+        package,
+        before.as_deref().unwrap_or("unknown"),
+        after.as_deref().unwrap_or("unknown")
+    );
+    println!("[tauri] {}", summary);
+    Ok(summary)
 }
 
 // ============== Dev Mode 命令 ==============
@@ -696,7 +5582,10 @@ async fn switch_to_dev_mode(window: tauri::WebviewWindow, app_handle: tauri::App
     println!("[tauri] Switching to dev mode: {}", dev_url);
     
     // 杀死 Python 后端，避免端口冲突
-    cleanup_python_process(&app_handle);
+    let turn = lifecycle_turn(&app_handle).await;
+    let handle = app_handle.clone();
+    let _ = tauri::async_runtime::spawn_blocking(move || cleanup_python_process(&handle)).await;
+    drop(turn);
     
     let url = Url::parse(&dev_url).map_err(|e| format!("Invalid URL: {}", e))?;
     window.navigate(url).map_err(|e| format!("Navigation failed: {}", e))?;
@@ -718,15 +5607,18 @@ async fn switch_to_release_mode(window: tauri::WebviewWindow, app_handle: tauri:
     
     println!("[tauri] Switching to release mode: {}", bundled_url);
     
-    // 如果是主实例，重新启动 Python 后端
-    let is_primary = if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
-        state.lock().unwrap().is_primary()
+    // 如果是主实例，重新启动 Python 后端（已通过 disable_and_stop_python 禁用时跳过）
+    let (is_primary, disabled) = if let Some(state) = app_handle.try_state::<Arc<Mutex<PythonProcess>>>() {
+        let guard = lock_process(&state);
+        (guard.is_primary(), guard.is_disabled())
     } else {
-        false
+        (false, false)
     };
     
-    if is_primary {
-        let _ = spawn_python_backend(app_handle.clone(), true);
+    if is_primary && !disabled {
+        let _turn = lifecycle_turn(&app_handle).await;
+        let handle = app_handle.clone();
+        let _ = tauri::async_runtime::spawn_blocking(move || spawn_python_backend(handle, true, StartReason::UserCommand)).await;
     }
     
     // 使用 WebviewUrl::App 来导航回打包的静态资源
@@ -763,9 +5655,53 @@ fn set_dev_url(app_handle: tauri::AppHandle, url: String) -> Result<String, Stri
 
 // ============== 应用入口 ==============
 
+/// 等待应用自身收到终止信号，返回信号名
+#[cfg(unix)]
+async fn wait_for_termination_signal() -> &'static str {
+    use tokio::signal::unix::{signal, SignalKind};
+    
+    let (Ok(mut interrupt), Ok(mut terminate)) = (signal(SignalKind::interrupt()), signal(SignalKind::terminate())) else {
+        println!("[tauri] Failed to install signal handlers");
+        return std::future::pending().await;
+    };
+    tokio::select! {
+        _ = interrupt.recv() => "SIGINT",
+        _ = terminate.recv() => "SIGTERM",
+    }
+}
+
+/// 等待应用自身收到终止信号，返回信号名（Windows：Ctrl-C、关闭控制台、系统关机）
+#[cfg(windows)]
+async fn wait_for_termination_signal() -> &'static str {
+    use tokio::signal::windows::{ctrl_close, ctrl_shutdown};
+    
+    let (Ok(mut close), Ok(mut shutdown)) = (ctrl_close(), ctrl_shutdown()) else {
+        println!("[tauri] Failed to install console control handlers");
+        return std::future::pending().await;
+    };
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => "CTRL_C",
+        _ = close.recv() => "CTRL_CLOSE",
+        _ = shutdown.recv() => "CTRL_SHUTDOWN",
+    }
+}
+
+/// 应用被信号终止（终端 Ctrl-C、系统关机）时先处理后端再退出
+///
+/// 这类退出不一定经过窗口关闭或 RunEvent::Exit，不处理会留下孤儿后端进程。
+fn start_signal_handler(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let signal = wait_for_termination_signal().await;
+        println!("[tauri] Received {}, cleanup...", signal);
+        let handle = app_handle.clone();
+        let _ = tauri::async_runtime::spawn_blocking(move || release_python_process(&handle)).await;
+        app_handle.exit(0);
+    });
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let config = PythonConfig::load();
+    let loaded = PythonConfig::load_detailed(None);
     
     tauri::Builder::default()
         .plugin(tauri_plugin_http::init())
@@ -777,25 +5713,56 @@ pub fn run() {
             let is_dev_mode = dev_mode.is_dev_mode;
             let dev_url = dev_mode.dev_url.clone();
 
-            app.manage(Arc::new(Mutex::new(PythonProcess::new(config.clone()))));
+            let mut python_process = PythonProcess::new(loaded.config.clone());
+            python_process.set_config_warnings(loaded.warnings.clone());
+            python_process.set_config_path(loaded.path.clone());
+            python_process.set_configured_python_path(loaded.configured_python_path.clone());
+            app.manage(Arc::new(Mutex::new(python_process)));
+            app.manage(LifecycleQueue::default());
+            app.manage(LogCaptureState::default());
             app.manage(Arc::new(Mutex::new(dev_mode)));
             
+            for warning in &loaded.warnings {
+                let _ = app.emit(events::CONFIG_WARNING, warning);
+            }
+            
+            start_signal_handler(app.handle().clone());
+            
             let app_handle = app.handle().clone();
             if let Some(window) = app.get_webview_window("main") {
                 window.on_window_event(move |event| {
                     if matches!(event, tauri::WindowEvent::CloseRequested { .. } | tauri::WindowEvent::Destroyed) {
                         println!("[tauri] Window closing, cleanup...");
-                        cleanup_python_process(&app_handle);
+                        release_python_process(&app_handle);
                     }
                 });
             }
             
+            if loaded.config.resource_monitor {
+                start_resource_monitor(app.handle().clone(), loaded.config.resource_monitor_interval_ms);
+            }
+            if loaded.config.watch_config {
+                match loaded.path.clone() {
+                    Some(path) => start_config_watcher(app.handle().clone(), path),
+                    None => println!("[tauri] No config file loaded, watch_config ignored"),
+                }
+            }
+            if let Some(timeout_ms) = loaded.config.idle_timeout_ms {
+                start_idle_watcher(app.handle().clone(), timeout_ms);
+            }
+            if loaded.config.heartbeat_interval_ms > 0 {
+                start_heartbeat(app.handle().clone(), loaded.config.heartbeat_interval_ms);
+            }
+            if let Some(port) = loaded.config.metrics_port {
+                start_metrics_server(app.handle().clone(), port);
+            }
+            
             // 检测是否是主实例
             let is_primary = try_acquire_primary_lock();
             
             // 更新状态
             if let Some(state) = app.try_state::<Arc<Mutex<PythonProcess>>>() {
-                state.lock().unwrap().set_primary(is_primary);
+                lock_process(&state).set_primary(is_primary);
             }
             
             // 启动 Python 后端（如果不是 Dev 模式）
@@ -807,11 +5774,38 @@ pub fn run() {
                     let url = Url::parse(&dev_url).expect("Invalid dev URL");
                     let _ = window.navigate(url);
                 }
+            } else if !loaded.config.auto_start {
+                println!("[tauri] Backend auto start disabled, waiting for manual start");
             } else {
                 println!("[tauri] Starting Python backend (primary: {})...", is_primary);
-                match spawn_python_backend(app_handle, is_primary) {
-                    Ok(port) => println!("[tauri] Python backend ready on port {}", port),
-                    Err(e) => eprintln!("[tauri] Failed to start Python backend: {}", e),
+                let start_initial = move || {
+                    // 与前端在启动期间发出的 start_python / restart_python 排队，避免同时启动两个后端；
+                    // 引导对话框的重试会自己排队，显示前先释放轮次
+                    let result = {
+                        let _turn = lifecycle_turn_blocking(&app_handle);
+                        spawn_python_backend(app_handle.clone(), is_primary, StartReason::Initial)
+                    };
+                    match result {
+                        Ok(info) => println!("[tauri] Python backend ready on port {}", info.port),
+                        Err(e) => {
+                            eprintln!("[tauri] Failed to start Python backend: {}", e.message);
+                            show_setup_dialog(&app_handle, &e);
+                        }
+                    }
+                };
+                let splash_shown = loaded.config.splash
+                    && match show_splash(app) {
+                        Ok(()) => true,
+                        Err(e) => {
+                            eprintln!("[tauri] Failed to create splash window: {}", e);
+                            false
+                        }
+                    };
+                if splash_shown {
+                    // 在后台启动，让事件循环先把启动画面渲染出来
+                    std::thread::spawn(start_initial);
+                } else {
+                    start_initial();
                 }
             }
             
@@ -820,14 +5814,59 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             greet,
             start_python,
+            note_backend_activity,
             shutdown_python,
+            shutdown_python_if_idle,
+            relaunch_app,
+            interrupt_python,
+            get_python_fd_count,
+            start_log_capture,
+            stop_log_capture,
+            get_port_owners,
+            disable_and_stop_python,
+            enable_python,
             start_sidecar,
             shutdown_sidecar,
             toggle_fullscreen,
+            list_windows,
+            set_window_fullscreen,
             get_python_config,
+            get_config_schema,
+            get_event_catalog,
+            get_config_warnings,
+            get_effective_config,
+            config_diff,
+            migrate_config,
+            set_active_profile,
+            save_python_config,
+            get_python_lifecycle,
+            get_auto_start,
+            set_auto_start,
+            pick_python_interpreter,
             get_backend_port,
+            check_port_connectable,
             get_instance_status,
+            get_python_status,
+            wait_for_python_state,
+            get_startup_progress,
+            recover_process_state,
             get_python_log_file,
+            query_python_logs,
+            list_python_candidates,
+            check_python_imports,
+            run_python_script,
+            export_diagnostics,
+            copy_diagnostics,
+            run_aestiv_selftest,
+            copy_to_clipboard,
+            get_backend_log_level,
+            set_backend_log_level,
+            get_backend_workers,
+            set_backend_workers,
+            open_backend_docs,
+            get_backend_openapi,
+            upgrade_aestiv,
+            list_backend_routes,
             switch_to_dev_mode,
             switch_to_release_mode,
             get_dev_mode_status,
@@ -838,7 +5877,348 @@ pub fn run() {
         .run(|app_handle, event| {
             if matches!(event, RunEvent::ExitRequested { .. } | RunEvent::Exit) {
                 println!("[tauri] App exiting, cleanup...");
-                cleanup_python_process(&app_handle);
+                release_python_process(&app_handle);
             }
         });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 按文件扩展名序列化后再解析回 PythonConfig
+    fn round_trip(path: &str, config: &PythonConfig) -> PythonConfig {
+        let value = serde_json::to_value(config).unwrap();
+        let content = serialize_config_document(path, &value).unwrap();
+        let parsed = parse_config_document(path, &content).unwrap();
+        serde_json::from_value(parsed).unwrap()
+    }
+    
+    fn customized_config() -> PythonConfig {
+        serde_json::from_value(serde_json::json!({
+            "port": 9100,
+            "auth_token": "secret",
+            "launcher": ["uv", "run"],
+            "env": { "APP_MODE": "test" },
+            "kill_sequence": [{ "signal": "SIGTERM", "wait_ms": 100 }],
+            "readiness": { "http_poll": { "path": "/ready" } },
+        }))
+        .unwrap()
+    }
+    
+    fn assert_same(left: &PythonConfig, right: &PythonConfig) {
+        assert_eq!(serde_json::to_value(left).unwrap(), serde_json::to_value(right).unwrap());
+    }
+    
+    #[test]
+    fn json_config_round_trips() {
+        for config in [PythonConfig::default(), customized_config()] {
+            assert_same(&round_trip("python.json", &config), &config);
+        }
+    }
+    
+    #[test]
+    fn toml_config_round_trips() {
+        for config in [PythonConfig::default(), customized_config()] {
+            assert_same(&round_trip("python.toml", &config), &config);
+        }
+    }
+    
+    #[test]
+    fn toml_omits_null_fields() {
+        // 顶层 null（auth_token）和嵌套 null（readiness.http_poll.path）都要去掉
+        let config = PythonConfig {
+            readiness: ReadinessStrategy::HttpPoll { path: None },
+            ..PythonConfig::default()
+        };
+        let value = serde_json::to_value(&config).unwrap();
+        let content = serialize_config_document("python.toml", &value).unwrap();
+        assert!(!content.contains("auth_token"));
+        
+        let parsed = parse_config_document("python.toml", &content).unwrap();
+        assert_eq!(parsed["readiness"], serde_json::json!({ "http_poll": {} }));
+        let config: PythonConfig = serde_json::from_value(parsed).unwrap();
+        assert_eq!(config.auth_token, None);
+        assert_eq!(config.readiness, ReadinessStrategy::HttpPoll { path: None });
+    }
+    
+    #[test]
+    fn event_names_only_appear_in_events_module() {
+        // 事件名必须通过 events:: 常量引用，源码中每个事件名的字符串字面量只能出现在定义处
+        let source = include_str!("lib.rs");
+        for descriptor in event_catalog() {
+            let literal = format!("\"{}\"", descriptor.name);
+            assert_eq!(
+                source.matches(&literal).count(),
+                1,
+                "event name {} is used as a raw string literal outside events::",
+                literal
+            );
+        }
+    }
+    
+    #[test]
+    fn event_catalog_covers_every_event() {
+        let source = include_str!("lib.rs");
+        let start = source.find("mod events {").unwrap();
+        let end = start + source[start..].find('}').unwrap();
+        let defined = source[start..end].matches("pub const").count();
+        assert_eq!(event_catalog().len(), defined);
+    }
+    
+    #[test]
+    fn strip_nulls_removes_nested_nulls() {
+        let stripped = strip_nulls(serde_json::json!({
+            "a": null,
+            "b": { "c": null, "d": 1 },
+            "e": [{ "f": null }],
+        }));
+        assert_eq!(stripped, serde_json::json!({ "b": { "d": 1 }, "e": [{}] }));
+    }
+    
+    #[test]
+    fn backend_cmdline_requires_interpreter_and_module() {
+        let cmd = |args: &[&str]| args.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let cases: &[(&[&str], bool)] = &[
+            (&["/home/u/aestivus/src-python/.venv/bin/python", "-m", "aestiv", "--port", "8009"], true),
+            (&["C:\\Python311\\python.exe", "-m", "aestiv"], true),
+            (&["python3.11", "-X", "utf8", "-m", "aestiv"], true),
+            // 只是路径里带有 aestiv 的程序
+            (&["/home/u/aestivus/src-tauri/target/debug/aestivus"], false),
+            (&["node", "/home/u/aestivus/node_modules/.bin/vite", "--port", "8009"], false),
+            (&["python", "/home/u/aestivus/src-python/main.py"], false),
+            // 模块名只是前缀
+            (&["python", "-m", "aestiv_tools"], false),
+            (&[], false),
+        ];
+        for (args, expected) in cases {
+            assert_eq!(is_backend_cmdline(&cmd(args), "aestiv"), *expected, "{:?}", args);
+        }
+    }
+    
+    #[test]
+    fn connect_base_url_maps_wildcards_and_brackets_ipv6() {
+        let url = |host: &str| PythonConfig { host: host.to_string(), ..PythonConfig::default() }.connect_base_url(8009);
+        assert_eq!(url("127.0.0.1"), "http://127.0.0.1:8009");
+        assert_eq!(url("0.0.0.0"), "http://127.0.0.1:8009");
+        assert_eq!(url("::"), "http://[::1]:8009");
+        assert_eq!(url("[::1]"), "http://[::1]:8009");
+        assert_eq!(url("fe80::1"), "http://[fe80::1]:8009");
+        assert_eq!(url("localhost"), "http://localhost:8009");
+    }
+    
+    #[test]
+    fn kill_step_action_parses_signals() {
+        let cases = [
+            ("SIGTERM", Some(KillAction::Term)),
+            ("SIGINT", Some(KillAction::Interrupt)),
+            ("SIGKILL", Some(KillAction::Kill)),
+            ("stdin:quit", Some(KillAction::Stdin("quit".to_string()))),
+            ("stdin:", Some(KillAction::Stdin(String::new()))),
+            // 信号名区分大小写，不支持的信号报错
+            ("sigterm", None),
+            ("SIGHUP", None),
+            ("", None),
+        ];
+        for (signal, expected) in cases {
+            let step = KillStep { signal: signal.to_string(), wait_ms: 0 };
+            assert_eq!(step.action().ok(), expected, "{:?}", signal);
+        }
+    }
+    
+    #[test]
+    fn python_module_name_rejects_code_injection() {
+        let cases = [
+            ("aestiv", true),
+            ("mypkg.server", true),
+            ("_private.mod2", true),
+            ("", false),
+            ("2fast", false),
+            ("a..b", false),
+            ("trailing.", false),
+            ("dash-name", false),
+            ("os; import shutil", false),
+            ("x'); print('y", false),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(is_python_module_name(name), expected, "{:?}", name);
+        }
+    }
+    
+    #[test]
+    fn active_requests_accepts_number_and_object_forms() {
+        let cases = [
+            (serde_json::json!(3), Some(3)),
+            (serde_json::json!({ "active_requests": 2 }), Some(2)),
+            (serde_json::json!({ "active": 5 }), Some(5)),
+            // 两个字段都有时优先 active_requests
+            (serde_json::json!({ "active_requests": 1, "active": 9 }), Some(1)),
+            (serde_json::json!(5_000_000_000u64), Some(u32::MAX)),
+            (serde_json::json!(-1), None),
+            (serde_json::json!("3"), None),
+            (serde_json::json!({ "busy": true }), None),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(parse_active_requests(&value), expected, "{}", value);
+        }
+    }
+    
+    #[test]
+    fn detect_log_level_reads_leading_level_word() {
+        let cases = [
+            ("INFO:     Uvicorn running on http://127.0.0.1:8009", Some("info")),
+            ("2024-01-01 12:00:00,123 - aestiv - ERROR - boom", Some("error")),
+            ("WARN disk almost full", Some("warning")),
+            ("WARNING: deprecated option", Some("warning")),
+            ("FATAL: cannot bind", Some("critical")),
+            ("DEBUG", Some("debug")),
+            // traceback 续行和小写单词不识别
+            ("Traceback (most recent call last):", None),
+            ("  File \"main.py\", line 1, in <module>", None),
+            ("info: lowercase", None),
+            // 只看行首附近的前几个单词
+            ("a b c d e f ERROR", None),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(detect_log_level(line), expected, "{:?}", line);
+        }
+    }
+    
+    #[test]
+    fn log_level_rank_validates_min_level() {
+        let cases = [
+            ("debug", Some(0)),
+            ("WARNING", Some(2)),
+            ("Critical", Some(4)),
+            ("warn", None),
+            ("", None),
+        ];
+        for (level, expected) in cases {
+            assert_eq!(log_level_rank(level).ok(), expected, "{:?}", level);
+        }
+    }
+    
+    #[test]
+    fn selftest_output_uses_last_json_line() {
+        let summary = |cases: Option<Vec<SelfTestCase>>| {
+            cases.map(|cases| cases.into_iter().map(|c| (c.name, c.passed, c.message)).collect::<Vec<_>>())
+        };
+        let cases: [(&str, Option<Vec<(String, bool, Option<String>)>>); 6] = [
+            (
+                r#"[{"name": "db", "passed": true}]"#,
+                Some(vec![("db".to_string(), true, None)]),
+            ),
+            (
+                "starting selftest\n{\"results\": [{\"name\": \"gpu\", \"ok\": false, \"error\": \"no device\"}]}\n\n",
+                Some(vec![("gpu".to_string(), false, Some("no device".to_string()))]),
+            ),
+            // 只解析最后一行，前面的 JSON 不算
+            ("[{\"name\": \"db\", \"passed\": true}]\ndone", None),
+            (r#"{"status": "ok"}"#, None),
+            (r#"[{"name": "db"}]"#, None),
+            ("", None),
+        ];
+        for (stdout, expected) in cases {
+            assert_eq!(summary(parse_selftest_output(stdout)), expected, "{:?}", stdout);
+        }
+    }
+    
+    #[test]
+    fn merge_config_values_merges_objects_and_replaces_others() {
+        let cases = [
+            (
+                serde_json::json!({ "port": 8009, "host": "127.0.0.1" }),
+                serde_json::json!({ "port": 9000 }),
+                serde_json::json!({ "port": 9000, "host": "127.0.0.1" }),
+            ),
+            // 嵌套对象逐字段合并
+            (
+                serde_json::json!({ "env": { "A": "1", "B": "2" } }),
+                serde_json::json!({ "env": { "B": "3", "C": "4" } }),
+                serde_json::json!({ "env": { "A": "1", "B": "3", "C": "4" } }),
+            ),
+            // 数组整体替换，不拼接
+            (
+                serde_json::json!({ "launcher": ["uv", "run"] }),
+                serde_json::json!({ "launcher": ["pipenv"] }),
+                serde_json::json!({ "launcher": ["pipenv"] }),
+            ),
+            // null 也会覆盖
+            (
+                serde_json::json!({ "auth_token": "secret" }),
+                serde_json::json!({ "auth_token": null }),
+                serde_json::json!({ "auth_token": null }),
+            ),
+            (
+                serde_json::json!({ "readiness": "tcp_connect" }),
+                serde_json::json!({ "readiness": { "http_poll": {} } }),
+                serde_json::json!({ "readiness": { "http_poll": {} } }),
+            ),
+        ];
+        for (mut base, overlay, expected) in cases {
+            merge_config_values(&mut base, &overlay);
+            assert_eq!(base, expected, "overlay {}", overlay);
+        }
+    }
+    
+    #[test]
+    fn select_profile_document_picks_requested_or_active_profile() {
+        let profiles = serde_json::json!({
+            "active": "dev",
+            "profiles": {
+                "dev": { "port": 8009 },
+                "prod": { "port": 80 },
+            },
+            "extra": true,
+        });
+        let cases = [
+            (serde_json::json!({ "port": 1 }), None, Some((serde_json::json!({ "port": 1 }), None))),
+            (profiles.clone(), None, Some((serde_json::json!({ "port": 8009 }), Some("dev")))),
+            (profiles.clone(), Some("prod"), Some((serde_json::json!({ "port": 80 }), Some("prod")))),
+            (profiles, Some("staging"), None),
+            (serde_json::json!({ "profiles": [] }), None, None),
+            (serde_json::json!({ "profiles": {} }), None, None),
+        ];
+        for (value, profile, expected) in cases {
+            let selected = select_profile_document(value.clone(), profile)
+                .ok()
+                .map(|(document, name, _)| (document, name));
+            let expected = expected.map(|(document, name)| (document, name.map(|n| n.to_string())));
+            assert_eq!(selected, expected, "{} (profile {:?})", value, profile);
+        }
+    }
+    
+    #[test]
+    fn select_profile_document_reports_unknown_top_level_keys() {
+        let value = serde_json::json!({ "active": "dev", "profiles": { "dev": {} }, "extra": true });
+        let (_, _, unknown) = select_profile_document(value, None).unwrap();
+        assert_eq!(unknown, vec!["extra".to_string()]);
+    }
+    
+    #[test]
+    fn import_error_distinguishes_missing_from_broken() {
+        let traceback = |error: &str| format!("Traceback (most recent call last):\n  File \"<string>\", line 1\n{}\n", error);
+        let cases = [
+            (traceback("ModuleNotFoundError: No module named 'aestiv'"), "aestiv", PackageStatus::NotInstalled),
+            // 点分模块名缺少顶层包
+            (traceback("ModuleNotFoundError: No module named 'mypkg'"), "mypkg.server", PackageStatus::NotInstalled),
+            // Python 2 风格的报错不带引号
+            (traceback("ImportError: No module named aestiv"), "aestiv", PackageStatus::NotInstalled),
+            (String::new(), "aestiv", PackageStatus::NotInstalled),
+            // 后端模块存在，但依赖缺失或初始化失败
+            (
+                traceback("ModuleNotFoundError: No module named 'numpy'"),
+                "aestiv",
+                PackageStatus::Broken("ModuleNotFoundError: No module named 'numpy'".to_string()),
+            ),
+            (
+                traceback("SyntaxError: invalid syntax"),
+                "aestiv",
+                PackageStatus::Broken("SyntaxError: invalid syntax".to_string()),
+            ),
+        ];
+        for (stderr, module, expected) in cases {
+            assert_eq!(classify_import_error(module, &stderr), expected, "{:?}", stderr);
+        }
+    }
+}